@@ -1,8 +1,7 @@
-cd data
-python2 karz.py
-cd ..
+cargo build --release
+./target/release/maxflow generate ./data
 
 files="./data/*.in"
 for filepath in $files; do
-  ./target/release/maxflow $filepath
+  ./target/release/maxflow $filepath $1
 done
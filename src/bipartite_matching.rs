@@ -0,0 +1,86 @@
+use crate::bipartite_push_relabel_highest_label::BipartitePushRelabelHighestLabel;
+use crate::error::MaxFlowError;
+use crate::graph::{Cost, Graph};
+use crate::min_cost_flow::MinCostFlow;
+
+// matching between left nodes 0..num_left_nodes and right nodes 0..num_right_nodes
+// the source, the sink and the unit edges around them are added internally:
+// left u is node u, right v is node num_left_nodes + v, then source and sink
+pub struct BipartiteMatching {
+    num_left_nodes: usize,
+    num_right_nodes: usize,
+    edges: Vec<(usize, usize)>,
+    weights: Vec<Cost>, // by edge index, 1 for unweighted edges
+}
+
+impl BipartiteMatching {
+    pub fn new(num_left_nodes: usize, num_right_nodes: usize, edges: &[(usize, usize)]) -> Result<Self, MaxFlowError> {
+        let weighted_edges: Vec<_> = edges.iter().map(|&(left, right)| (left, right, 1)).collect();
+        Self::with_weights(num_left_nodes, num_right_nodes, &weighted_edges)
+    }
+
+    pub fn with_weights(num_left_nodes: usize, num_right_nodes: usize, edges: &[(usize, usize, Cost)]) -> Result<Self, MaxFlowError> {
+        for &(left, right, _) in edges {
+            if left >= num_left_nodes {
+                return Err(MaxFlowError::NodeOutOfRange {
+                    node: left,
+                    num_nodes: num_left_nodes,
+                });
+            }
+            if right >= num_right_nodes {
+                return Err(MaxFlowError::NodeOutOfRange {
+                    node: right,
+                    num_nodes: num_right_nodes,
+                });
+            }
+        }
+        Ok(BipartiteMatching {
+            num_left_nodes,
+            num_right_nodes,
+            edges: edges.iter().map(|&(left, right, _)| (left, right)).collect(),
+            weights: edges.iter().map(|&(_, _, weight)| weight).collect(),
+        })
+    }
+
+    // maximum cardinality matching with the highest label push-relabel, return (left, right) pairs
+    pub fn maximum_matching(&self) -> Result<Vec<(usize, usize)>, MaxFlowError> {
+        let (mut graph, source, sink) = self.to_graph(false);
+        // the bipartite solver wants the smaller side as its left side
+        let n1 = self.num_left_nodes.min(self.num_right_nodes);
+        let n2 = self.num_left_nodes.max(self.num_right_nodes);
+        // the graph is bipartite by construction
+        let mut solver = BipartitePushRelabelHighestLabel::new_unchecked(n1, n2, &mut graph)?;
+        solver.solve(source, sink)?;
+        Ok(self.matched_pairs(&graph))
+    }
+
+    // matching of maximum total weight, not necessarily of maximum cardinality, return (pairs, weight)
+    // min cost flow with the negated weights, stopped once no augmenting path increases the weight
+    pub fn maximum_weight_matching(&self) -> Result<(Vec<(usize, usize)>, Cost), MaxFlowError> {
+        let (mut graph, source, sink) = self.to_graph(true);
+        let (_, cost) = MinCostFlow::new(&mut graph)?.solve_min_cost(source, sink)?;
+        Ok((self.matched_pairs(&graph), -cost))
+    }
+
+    // the edges of the matching have edge indices 0..edges.len()
+    fn to_graph(&self, weighted: bool) -> (Graph, usize, usize) {
+        let (n1, n2) = (self.num_left_nodes, self.num_right_nodes);
+        let (source, sink) = (n1 + n2, n1 + n2 + 1);
+        let mut graph = Graph::with_num_nodes(n1 + n2 + 2);
+        for (edge_index, &(left, right)) in self.edges.iter().enumerate() {
+            let cost = if weighted { -self.weights[edge_index] } else { 0 };
+            graph.add_directed_edge_with_cost(left, n1 + right, 1, cost);
+        }
+        for u in 0..n1 {
+            graph.add_directed_edge(source, u, 1);
+        }
+        for v in 0..n2 {
+            graph.add_directed_edge(n1 + v, sink, 1);
+        }
+        (graph, source, sink)
+    }
+
+    fn matched_pairs(&self, graph: &Graph) -> Vec<(usize, usize)> {
+        (0..self.edges.len()).filter(|&edge_index| graph.get_edge(edge_index).flow > 0).map(|edge_index| self.edges[edge_index]).collect()
+    }
+}
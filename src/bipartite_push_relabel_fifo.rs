@@ -1,12 +1,18 @@
 #![allow(dead_code)]
 use std::collections::VecDeque;
 
+use crate::bipartition::{detect_bipartition, detect_bipartition_multi};
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
 use crate::graph::{Flow, Graph};
+use crate::max_flow_solver::MaxFlowSolver;
 
-pub struct BipartitePushRelabelFIFO<'a> {
-    pub graph: &'a mut Graph,
+pub struct BipartitePushRelabelFIFO<'a, F = Flow> {
+    pub graph: &'a mut Graph<F>,
     num_left_nodes: usize,
-    _num_right_nodes: usize,
+    num_right_nodes: usize,
+    checked: bool, // the sides are checked by every solve
+    distance_limit: usize, // nodes with a larger distance cannot reach sink
     alpha: usize,
     relabel_count: usize,
 
@@ -17,16 +23,36 @@ pub struct BipartitePushRelabelFIFO<'a> {
     distance_count: Vec<usize>,
 }
 
-impl<'a> BipartitePushRelabelFIFO<'a> {
-    pub fn new(num_left_nodes: usize, num_right_nodes: usize, graph: &'a mut Graph) -> Self {
-        assert!(num_left_nodes <= num_right_nodes);
-        graph.build();
-        let n = graph.num_nodes();
+impl<'a, F: Capacity> BipartitePushRelabelFIFO<'a, F> {
+    // every solve checks the sides against the terminals in O(n + m), a wrong split is an InvalidBipartition or NotBipartite error
+    pub fn new(num_left_nodes: usize, num_right_nodes: usize, graph: &'a mut Graph<F>) -> Result<Self, MaxFlowError> {
+        let mut solver = Self::new_unchecked(num_left_nodes, num_right_nodes, graph)?;
+        solver.checked = true;
+        Ok(solver)
+    }
+
+    // same as new without the check, for sides known to be right, since a wrong split silently gives a wrong flow
+    pub fn new_unchecked(num_left_nodes: usize, num_right_nodes: usize, graph: &'a mut Graph<F>) -> Result<Self, MaxFlowError> {
+        if num_left_nodes > num_right_nodes {
+            return Err(MaxFlowError::InvalidBipartition {
+                num_left_nodes,
+                num_right_nodes,
+            });
+        }
+        graph.build()?;
+        // an out copy would break the bipartition
+        if let Some(&node) = graph.split_nodes().first() {
+            return Err(MaxFlowError::NodeCapacityUnsupported { node });
+        }
+        let n = graph.num_inside_nodes();
 
-        BipartitePushRelabelFIFO {
+        Ok(BipartitePushRelabelFIFO {
             graph,
             num_left_nodes,
-            _num_right_nodes: num_right_nodes,
+            num_right_nodes,
+            checked: false,
+            // a path to sink alternates between the two sides, so it has at most 2 * num_left_nodes + 1 edges
+            distance_limit: (2 * num_left_nodes + 2).min(n),
             alpha: 0,
             relabel_count: 0,
 
@@ -35,66 +61,120 @@ impl<'a> BipartitePushRelabelFIFO<'a> {
 
             current_edge: vec![0; n],
             distance_count: vec![0; n + 1],
-        }
+        })
+    }
+
+    // the sides are detected from the graph, see detect_bipartition
+    pub fn with_detected_bipartition(graph: &'a mut Graph<F>, source: usize, sink: usize) -> Result<Self, MaxFlowError> {
+        let bipartition = detect_bipartition(graph, source, sink)?;
+        Self::new(bipartition.num_left_nodes(), bipartition.num_right_nodes(), graph)
     }
 
     pub fn set_alpha(&mut self, alpha: usize) {
         self.alpha = alpha;
     }
 
-    pub fn solve(&mut self, source: usize, sink: usize) -> Flow {
-        if source == sink || self.graph.num_nodes() == 0 || self.graph.num_edges() == 0 {
-            return 0 as Flow;
+    pub fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        self.solve_multi(&[source], &[sink])
+    }
+
+    // maximum flow from any of sources to any of sinks
+    // the nodes other than the terminals must form the bipartite graph
+    pub fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        self.rebuild()?;
+        self.graph.check_terminals(sources, sinks)?;
+        if self.graph.num_edges() == 0 {
+            return Ok(F::zero());
         }
-        self.pre_process(source, sink);
+        self.graph.check_capacity_overflow(sources)?;
+        // warm start from the flow of the last solve
+        self.graph.repair_flow(sources, sinks);
+        if self.checked || self.graph.validation() {
+            self.validate_bipartition(sources, sinks)?;
+        }
+        self.pre_process(sources, sinks);
 
         while let Some(u) = self.active_nodes.pop_front() {
-            assert_ne!(u, source);
-            assert_ne!(u, sink);
+            debug_assert!(!sources.contains(&u) && !sinks.contains(&u));
             self.in_que[u] = false;
             // there is no path from u to sink
-            if self.graph.distances[u] >= 2 * self.num_left_nodes {
+            if self.graph.distances[u] >= self.distance_limit {
                 continue;
             }
-            assert!(self.graph.excesses[u] > 0);
-            self.bi_discharge(u, sink);
+            debug_assert!(self.graph.excesses[u].is_positive());
+            self.bi_discharge(u);
 
             // global update heuristic
-            if self.alpha != 0 && self.relabel_count > self.alpha * self.graph.num_nodes() {
-                self.relabel_count = 0;
-                self.graph.update_distance_to_sink(source, sink);
+            if self.alpha != 0 && self.relabel_count > self.alpha * self.graph.num_inside_nodes() {
+                self.global_update(sources, sinks);
             }
         }
 
-        self.push_flow_excess_back_to_source(source, sink);
-        self.graph.excesses[sink]
+        self.graph.push_excess_back_to_sources(sources, sinks);
+        if self.graph.validation() {
+            self.graph.validate_flow(sources, sinks)?;
+        }
+        Ok(self.graph.flow_value(sinks))
     }
 
-    fn pre_process(&mut self, source: usize, sink: usize) {
-        self.graph.update_distance_to_sink(source, sink);
-        self.graph.distances[source] = 2 * self.num_left_nodes;
+    // build again if edges or nodes were added through graph_mut, see Graph::build
+    // added nodes must fit the sides given to new, which solve checks unless built by new_unchecked
+    fn rebuild(&mut self) -> Result<(), MaxFlowError> {
+        self.graph.build()?;
+        if let Some(&node) = self.graph.split_nodes().first() {
+            return Err(MaxFlowError::NodeCapacityUnsupported { node });
+        }
+        let n = self.graph.num_inside_nodes();
+        self.distance_limit = (2 * self.num_left_nodes + 2).min(n);
+        self.in_que.resize(n, false);
+        self.current_edge.resize(n, 0);
+        self.distance_count.resize(n + 1, 0);
+        Ok(())
+    }
 
-        for u in 0..self.graph.num_nodes() {
-            self.distance_count[self.graph.distances[u]] += 1;
-            self.current_edge[u] = self.graph.start[u];
+    // the distance limit is only valid if no path has more left nodes than num_left_nodes
+    pub(crate) fn validate_bipartition(&self, sources: &[usize], sinks: &[usize]) -> Result<(), MaxFlowError> {
+        let bipartition = detect_bipartition_multi(self.graph, sources, sinks)?;
+        if bipartition.num_left_nodes() > self.num_left_nodes {
+            return Err(MaxFlowError::InvalidBipartition {
+                num_left_nodes: self.num_left_nodes,
+                num_right_nodes: self.num_right_nodes,
+            });
         }
+        Ok(())
+    }
 
+    fn pre_process(&mut self, sources: &[usize], sinks: &[usize]) {
         // push from source
-        for i in self.graph.start[source]..self.graph.start[source + 1] {
-            let delta = self.graph.inside_edge_list[i].residual_capacity();
-            self.graph.push_flow(source, i, delta);
+        for &s in sources {
+            for i in self.graph.start[s]..self.graph.start[s + 1] {
+                let delta = self.graph.inside_edge_list[i].residual_capacity();
+                self.graph.push_flow(s, i, delta);
+            }
         }
+        self.global_update(sources, sinks);
+    }
 
-        for u in 0..self.graph.num_nodes() {
-            if u != source && u != sink && self.graph.excesses[u] > 0 as Flow {
-                self.enqueue(u);
-            }
+    // exact distances to sink, then every node with an excess is active again
+    fn global_update(&mut self, sources: &[usize], sinks: &[usize]) {
+        let n = self.graph.num_inside_nodes();
+        self.relabel_count = 0;
+        self.graph.global_relabel(sources, sinks, self.distance_limit, &mut self.distance_count);
+        self.current_edge.copy_from_slice(&self.graph.start[..n]);
+
+        self.active_nodes.clear();
+        self.in_que.fill(false);
+        // the terminals are never active
+        for &u in sources.iter().chain(sinks) {
+            self.in_que[u] = true;
+        }
+        for u in 0..self.graph.num_inside_nodes() {
+            self.enqueue(u);
         }
-        self.in_que[sink] = true;
     }
 
     fn enqueue(&mut self, u: usize) {
-        if self.in_que[u] || self.graph.excesses[u] <= 0 as Flow || self.graph.distances[u] >= 2 * self.num_left_nodes {
+        if self.in_que[u] || !self.graph.excesses[u].is_positive() || self.graph.distances[u] >= self.distance_limit {
             return;
         }
 
@@ -102,19 +182,30 @@ impl<'a> BipartitePushRelabelFIFO<'a> {
         self.active_nodes.push_back(u);
     }
 
-    fn bi_discharge(&mut self, u: usize, sink: usize) {
+    fn bi_discharge(&mut self, u: usize) {
         // push u -> v -> w
         let mut u_has_admissible_edge = false;
         for i in self.current_edge[u]..self.graph.start[u + 1] {
             self.current_edge[u] = i;
             let v = self.graph.inside_edge_list[i].to;
 
-            assert!(self.graph.excesses[u] > 0);
+            debug_assert!(self.graph.excesses[u].is_positive());
             if !self.graph.is_admissible_edge(u, i) {
                 continue;
             }
             u_has_admissible_edge = true;
 
+            // only the sinks have distance 0
+            if self.graph.distances[v] == 0 {
+                let delta = self.graph.excesses[u].min_capacity(self.graph.inside_edge_list[i].residual_capacity());
+                self.graph.push_flow(u, i, delta);
+                if self.graph.excesses[u].is_zero() {
+                    self.current_edge[u] = self.graph.start[u];
+                    return;
+                }
+                continue;
+            }
+
             let mut v_has_admissible_edge = false;
             for j in self.current_edge[v]..self.graph.start[v + 1] {
                 self.current_edge[v] = j;
@@ -126,123 +217,59 @@ impl<'a> BipartitePushRelabelFIFO<'a> {
                 v_has_admissible_edge = true;
 
                 let delta = self.graph.excesses[u]
-                    .min(self.graph.inside_edge_list[i].residual_capacity())
-                    .min(self.graph.inside_edge_list[j].residual_capacity());
-                assert!(delta > 0 as Flow);
+                    .min_capacity(self.graph.inside_edge_list[i].residual_capacity())
+                    .min_capacity(self.graph.inside_edge_list[j].residual_capacity());
+                debug_assert!(delta.is_positive());
 
                 // push u -> v -> w
                 self.graph.push_flow(u, i, delta);
                 self.graph.push_flow(v, j, delta);
 
-                if w != sink && self.graph.excesses[w] == delta {
-                    self.enqueue(w);
-                }
+                // enqueue ignores nodes that are already active and the terminals
+                self.enqueue(w);
 
-                if self.graph.excesses[u] == 0 as Flow {
+                if self.graph.excesses[u].is_zero() {
                     self.current_edge[u] = self.graph.start[u];
                     return;
                 }
 
-                if self.graph.inside_edge_list[i].residual_capacity() == 0 as Flow {
+                if self.graph.inside_edge_list[i].residual_capacity().is_zero() {
                     break;
                 }
             }
 
             // relabel
-            if !v_has_admissible_edge {
-                if self.distance_count[self.graph.distances[v]] == 1 {
-                    self.gap_relabeling(self.graph.distances[v]);
-                } else {
-                    self.relabel(v);
-                }
+            if !v_has_admissible_edge && self.graph.relabel(v, &mut self.distance_count) {
+                self.relabel_count += 1;
             }
             self.current_edge[v] = self.graph.start[v];
         }
 
         // relabel
-        if !u_has_admissible_edge {
-            if self.distance_count[self.graph.distances[u]] == 1 {
-                self.gap_relabeling(self.graph.distances[u]);
-            } else {
-                self.relabel(u);
-            }
+        if !u_has_admissible_edge && self.graph.relabel(u, &mut self.distance_count) {
+            self.relabel_count += 1;
         }
         self.current_edge[u] = self.graph.start[u];
-        if self.graph.excesses[u] > 0 as Flow {
+        if self.graph.excesses[u].is_positive() {
             self.enqueue(u);
         }
     }
+}
 
-    fn relabel(&mut self, u: usize) {
-        self.relabel_count += 1;
-        self.distance_count[self.graph.distances[u]] -= 1;
-
-        let pre_distance = self.graph.distances[u];
-        self.graph.distances[u] = self
-            .graph
-            .neighbors(u)
-            .filter(|edge| edge.residual_capacity() > 0 as Flow)
-            .map(|edge| self.graph.distances[edge.to] + 1)
-            .min()
-            .unwrap_or(self.graph.num_nodes())
-            .min(self.graph.num_nodes());
-
-        assert!(self.graph.distances[u] > pre_distance);
-        self.distance_count[self.graph.distances[u]] += 1;
-    }
-
-    // gap relabeling heuristic
-    // set distance[u] >= k to distance[u] = n
-    // O(n)
-    fn gap_relabeling(&mut self, k: usize) {
-        for u in 0..self.graph.num_nodes() {
-            if self.graph.distances[u] >= k {
-                self.distance_count[self.graph.distances[u]] -= 1;
-                self.graph.distances[u] = self.graph.distances[u].max(self.graph.num_nodes());
-                self.distance_count[self.graph.distances[u]] += 1;
-            }
-        }
+impl<F: Capacity> MaxFlowSolver<F> for BipartitePushRelabelFIFO<'_, F> {
+    fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        BipartitePushRelabelFIFO::solve(self, source, sink)
     }
 
-    fn push_flow_excess_back_to_source(&mut self, source: usize, sink: usize) {
-        for u in 0..self.graph.num_nodes() {
-            self.current_edge[u] = self.graph.start[u];
-        }
-
-        for u in 0..self.graph.num_nodes() {
-            if u == source || u == sink {
-                continue;
-            }
-            while self.graph.excesses[u] > 0 as Flow {
-                // u から source への逆辺を使ったパスをみつける
-                let mut visited = vec![false; self.graph.num_nodes()];
-                self.dfs(u, source, sink, self.graph.excesses[u], &mut visited);
-            }
-            assert_eq!(self.graph.excesses[u], 0 as Flow);
-        }
+    fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        BipartitePushRelabelFIFO::solve_multi(self, sources, sinks)
     }
 
-    fn dfs(&mut self, u: usize, source: usize, sink: usize, flow: Flow, visited: &mut Vec<bool>) -> Flow {
-        if u == source {
-            return flow;
-        }
-        visited[u] = true;
-
-        // for i in self.graph.start[u]..self.graph.start[u + 1] {
-        for i in self.current_edge[u]..self.graph.start[u + 1] {
-            self.current_edge[u] = i;
-            let to = self.graph.inside_edge_list[i].to;
-            let residual_capacity = self.graph.inside_edge_list[i].residual_capacity();
-            if visited[to] || residual_capacity == 0 as Flow {
-                continue;
-            }
+    fn graph(&self) -> &Graph<F> {
+        self.graph
+    }
 
-            let delta = self.dfs(to, source, sink, flow.min(residual_capacity), visited);
-            if delta > 0 as Flow {
-                self.graph.push_flow(u, i, delta);
-                return delta;
-            }
-        }
-        0 as Flow
+    fn graph_mut(&mut self) -> &mut Graph<F> {
+        self.graph
     }
 }
@@ -1,10 +1,16 @@
 #![allow(dead_code)]
+use crate::bipartition::{detect_bipartition, detect_bipartition_multi};
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
 use crate::graph::{Flow, Graph};
+use crate::max_flow_solver::MaxFlowSolver;
 
-pub struct BipartitePushRelabelHighestLabel<'a> {
-    pub graph: &'a mut Graph,
+pub struct BipartitePushRelabelHighestLabel<'a, F = Flow> {
+    pub graph: &'a mut Graph<F>,
     num_left_nodes: usize,
-    _num_right_nodes: usize,
+    num_right_nodes: usize,
+    checked: bool, // the sides are checked by every solve
+    distance_limit: usize, // nodes with a larger distance cannot reach sink
     alpha: usize,
     relabel_count: usize,
 
@@ -16,16 +22,36 @@ pub struct BipartitePushRelabelHighestLabel<'a> {
     distance_count: Vec<usize>,
 }
 
-impl<'a> BipartitePushRelabelHighestLabel<'a> {
-    pub fn new(num_left_nodes: usize, num_right_nodes: usize, graph: &'a mut Graph) -> Self {
-        assert!(num_left_nodes <= num_right_nodes);
-        graph.build();
-        let n = graph.num_nodes();
+impl<'a, F: Capacity> BipartitePushRelabelHighestLabel<'a, F> {
+    // every solve checks the sides against the terminals in O(n + m), a wrong split is an InvalidBipartition or NotBipartite error
+    pub fn new(num_left_nodes: usize, num_right_nodes: usize, graph: &'a mut Graph<F>) -> Result<Self, MaxFlowError> {
+        let mut solver = Self::new_unchecked(num_left_nodes, num_right_nodes, graph)?;
+        solver.checked = true;
+        Ok(solver)
+    }
 
-        BipartitePushRelabelHighestLabel {
+    // same as new without the check, for sides known to be right, since a wrong split silently gives a wrong flow
+    pub fn new_unchecked(num_left_nodes: usize, num_right_nodes: usize, graph: &'a mut Graph<F>) -> Result<Self, MaxFlowError> {
+        if num_left_nodes > num_right_nodes {
+            return Err(MaxFlowError::InvalidBipartition {
+                num_left_nodes,
+                num_right_nodes,
+            });
+        }
+        graph.build()?;
+        // an out copy would break the bipartition
+        if let Some(&node) = graph.split_nodes().first() {
+            return Err(MaxFlowError::NodeCapacityUnsupported { node });
+        }
+        let n = graph.num_inside_nodes();
+
+        Ok(BipartitePushRelabelHighestLabel {
             graph,
             num_left_nodes,
-            _num_right_nodes: num_right_nodes,
+            num_right_nodes,
+            checked: false,
+            // a path to sink alternates between the two sides, so it has at most 2 * num_left_nodes + 1 edges
+            distance_limit: (2 * num_left_nodes + 2).min(n),
 
             alpha: 0,
             relabel_count: 0,
@@ -36,20 +62,133 @@ impl<'a> BipartitePushRelabelHighestLabel<'a> {
 
             current_edge: vec![0; n],
             distance_count: vec![0; n + 1],
-        }
+        })
+    }
+
+    // the sides are detected from the graph, see detect_bipartition
+    pub fn with_detected_bipartition(graph: &'a mut Graph<F>, source: usize, sink: usize) -> Result<Self, MaxFlowError> {
+        let bipartition = detect_bipartition(graph, source, sink)?;
+        Self::new(bipartition.num_left_nodes(), bipartition.num_right_nodes(), graph)
     }
 
     pub fn set_alpha(&mut self, alpha: usize) {
         self.alpha = alpha;
     }
 
-    pub fn solve(&mut self, source: usize, sink: usize) -> Flow {
-        if source == sink || self.graph.num_nodes() == 0 || self.graph.num_edges() == 0 {
-            return 0 as Flow;
+    pub fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        self.solve_multi(&[source], &[sink])
+    }
+
+    // maximum flow from any of sources to any of sinks
+    // the nodes other than the terminals must form the bipartite graph
+    pub fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        self.rebuild()?;
+        self.graph.check_terminals(sources, sinks)?;
+        if self.graph.num_edges() == 0 {
+            return Ok(F::zero());
+        }
+        self.graph.check_capacity_overflow(sources)?;
+        // warm start from the flow of the last solve
+        self.graph.repair_flow(sources, sinks);
+        if self.checked || self.graph.validation() {
+            self.validate_bipartition(sources, sinks)?;
+        }
+
+        self.pre_process(sources, sinks);
+        self.discharge_active_nodes(sources, sinks);
+
+        self.graph.push_excess_back_to_sources(sources, sinks);
+        if self.graph.validation() {
+            self.graph.validate_flow(sources, sinks)?;
+        }
+        Ok(self.graph.flow_value(sinks))
+    }
+
+    // build again if edges or nodes were added through graph_mut, see Graph::build
+    // added nodes must fit the sides given to new, which solve checks unless built by new_unchecked
+    fn rebuild(&mut self) -> Result<(), MaxFlowError> {
+        self.graph.build()?;
+        if let Some(&node) = self.graph.split_nodes().first() {
+            return Err(MaxFlowError::NodeCapacityUnsupported { node });
+        }
+        let n = self.graph.num_inside_nodes();
+        self.distance_limit = (2 * self.num_left_nodes + 2).min(n);
+        self.buckets.resize(n, Vec::new());
+        self.in_bucket.resize(n, false);
+        self.current_edge.resize(n, 0);
+        self.distance_count.resize(n + 1, 0);
+        Ok(())
+    }
+
+    // the distance limit is only valid if no path has more left nodes than num_left_nodes
+    pub(crate) fn validate_bipartition(&self, sources: &[usize], sinks: &[usize]) -> Result<(), MaxFlowError> {
+        let bipartition = detect_bipartition_multi(self.graph, sources, sinks)?;
+        if bipartition.num_left_nodes() > self.num_left_nodes {
+            return Err(MaxFlowError::InvalidBipartition {
+                num_left_nodes: self.num_left_nodes,
+                num_right_nodes: self.num_right_nodes,
+            });
         }
+        Ok(())
+    }
+
+    // phase 1 only, the result is a maximum preflow
+    // the distance labels are computed from scratch
+    pub(crate) fn start_phase1(&mut self, sources: &[usize], sinks: &[usize]) {
+        self.pre_process(sources, sinks);
+        self.discharge_active_nodes(sources, sinks);
+    }
+
+    // phase 1 only, starting from the preflow and the distance labels of the last run
+    // the labels stay valid as long as only the capacities of source edges grew and those of sink edges shrank since then
+    pub(crate) fn resume_phase1(&mut self, sources: &[usize], sinks: &[usize]) {
+        self.graph.count_distances(&mut self.distance_count);
+        self.saturate_source_edges(sources, sinks);
+        self.discharge_active_nodes(sources, sinks);
+    }
 
-        self.pre_process(source, sink);
+    fn pre_process(&mut self, sources: &[usize], sinks: &[usize]) {
+        self.graph.global_relabel(sources, sinks, self.distance_limit, &mut self.distance_count);
+        self.saturate_source_edges(sources, sinks);
+    }
+
+    // push from source and activate every node with an excess
+    fn saturate_source_edges(&mut self, sources: &[usize], sinks: &[usize]) {
+        for &s in sources {
+            for i in self.graph.start[s]..self.graph.start[s + 1] {
+                let delta = self.graph.inside_edge_list[i].residual_capacity();
+                self.graph.push_flow(s, i, delta);
+            }
+        }
+        self.reset_active_nodes(sources, sinks);
+    }
 
+    // global update heuristic
+    // exact distances to sink, then the buckets are built again for them
+    fn global_update(&mut self, sources: &[usize], sinks: &[usize]) {
+        self.graph.global_relabel(sources, sinks, self.distance_limit, &mut self.distance_count);
+        self.reset_active_nodes(sources, sinks);
+    }
+
+    // activate every node with an excess, after the distances changed
+    fn reset_active_nodes(&mut self, sources: &[usize], sinks: &[usize]) {
+        let n = self.graph.num_inside_nodes();
+        self.relabel_count = 0;
+        self.buckets.iter_mut().for_each(|bucket| bucket.clear());
+        self.in_bucket.fill(false);
+        self.bucket_idx = 0;
+        self.current_edge.copy_from_slice(&self.graph.start[..n]);
+
+        // the terminals are never active
+        for &u in sources.iter().chain(sinks) {
+            self.in_bucket[u] = true;
+        }
+        for u in 0..self.graph.num_inside_nodes() {
+            self.enqueue(u);
+        }
+    }
+
+    fn discharge_active_nodes(&mut self, sources: &[usize], sinks: &[usize]) {
         loop {
             if self.buckets[self.bucket_idx].is_empty() {
                 if self.bucket_idx == 0 {
@@ -61,38 +200,17 @@ impl<'a> BipartitePushRelabelHighestLabel<'a> {
 
             let u = self.buckets[self.bucket_idx].pop().unwrap();
             self.in_bucket[u] = false;
-            self.bi_discharge(u, sink);
-        }
-
-        self.push_flow_excess_back_to_source(source, sink);
+            self.bi_discharge(u);
 
-        self.graph.excesses[sink]
-    }
-
-    fn pre_process(&mut self, source: usize, sink: usize) {
-        self.graph.update_distance_to_sink(source, sink);
-        self.graph.distances[source] = 2 * self.num_left_nodes;
-
-        for u in 0..self.graph.num_nodes() {
-            self.distance_count[self.graph.distances[u]] += 1;
-            self.current_edge[u] = self.graph.start[u];
-        }
-
-        for i in self.graph.start[source]..self.graph.start[source + 1] {
-            let delta = self.graph.inside_edge_list[i].residual_capacity();
-            self.graph.push_flow(source, i, delta);
-        }
-
-        for u in 0..self.graph.num_nodes() {
-            if u != source && u != sink && self.graph.excesses[u] > 0 as Flow {
-                self.enqueue(u);
+            // global update heuristic
+            if self.alpha != 0 && self.relabel_count > self.alpha * self.graph.num_inside_nodes() {
+                self.global_update(sources, sinks);
             }
         }
-        self.in_bucket[sink] = true;
     }
 
     fn enqueue(&mut self, u: usize) {
-        if self.in_bucket[u] || self.graph.excesses[u] <= 0 as Flow || self.graph.distances[u] >= 2 * self.num_left_nodes {
+        if self.in_bucket[u] || !self.graph.excesses[u].is_positive() || self.graph.distances[u] >= self.distance_limit {
             return;
         }
 
@@ -101,19 +219,29 @@ impl<'a> BipartitePushRelabelHighestLabel<'a> {
         self.bucket_idx = self.bucket_idx.max(self.graph.distances[u]);
     }
 
-    fn bi_discharge(&mut self, u: usize, sink: usize) {
+    fn bi_discharge(&mut self, u: usize) {
         // push u -> v -> w
         let mut u_has_admissible_edge = false;
         for i in self.current_edge[u]..self.graph.start[u + 1] {
             self.current_edge[u] = i;
             let v = self.graph.inside_edge_list[i].to;
 
-            assert!(self.graph.excesses[u] > 0);
+            debug_assert!(self.graph.excesses[u].is_positive());
             if !self.graph.is_admissible_edge(u, i) {
                 continue;
             }
             u_has_admissible_edge = true;
 
+            // only the sinks have distance 0
+            if self.graph.distances[v] == 0 {
+                let delta = self.graph.excesses[u].min_capacity(self.graph.inside_edge_list[i].residual_capacity());
+                self.graph.push_flow(u, i, delta);
+                if self.graph.excesses[u].is_zero() {
+                    return;
+                }
+                continue;
+            }
+
             let mut v_has_admissible_edge = false;
             for j in self.current_edge[v]..self.graph.start[v + 1] {
                 self.current_edge[v] = j;
@@ -125,118 +253,59 @@ impl<'a> BipartitePushRelabelHighestLabel<'a> {
                 v_has_admissible_edge = true;
 
                 let delta = self.graph.excesses[u]
-                    .min(self.graph.inside_edge_list[i].residual_capacity())
-                    .min(self.graph.inside_edge_list[j].residual_capacity());
-                assert!(delta > 0 as Flow);
+                    .min_capacity(self.graph.inside_edge_list[i].residual_capacity())
+                    .min_capacity(self.graph.inside_edge_list[j].residual_capacity());
+                debug_assert!(delta.is_positive());
 
                 // push u -> v -> w
                 self.graph.push_flow(u, i, delta);
                 self.graph.push_flow(v, j, delta);
 
-                if w != sink && self.graph.excesses[w] == delta {
-                    self.enqueue(w);
-                }
+                // enqueue ignores nodes that are already active and the terminals
+                self.enqueue(w);
 
-                if self.graph.excesses[u] == 0 as Flow {
+                if self.graph.excesses[u].is_zero() {
                     return;
                 }
 
-                if self.graph.inside_edge_list[i].residual_capacity() == 0 as Flow {
+                if self.graph.inside_edge_list[i].residual_capacity().is_zero() {
                     break;
                 }
             }
 
             // relabel
-            if !v_has_admissible_edge {
-                if self.distance_count[self.graph.distances[v]] == 1 {
-                    self.gap_relabeling(self.graph.distances[v]);
-                } else {
-                    self.relabel(v);
-                }
+            if !v_has_admissible_edge && self.graph.relabel(v, &mut self.distance_count) {
+                self.relabel_count += 1;
             }
             self.current_edge[v] = self.graph.start[v];
         }
 
         // relabel
-        if !u_has_admissible_edge {
-            if self.distance_count[self.graph.distances[u]] == 1 {
-                self.gap_relabeling(self.graph.distances[u]);
-            } else {
-                self.relabel(u);
-            }
+        if !u_has_admissible_edge && self.graph.relabel(u, &mut self.distance_count) {
+            self.relabel_count += 1;
         }
         self.current_edge[u] = self.graph.start[u];
 
-        if self.graph.excesses[u] > 0 as Flow {
+        if self.graph.excesses[u].is_positive() {
             self.enqueue(u);
         }
     }
+}
 
-    fn relabel(&mut self, u: usize) {
-        self.relabel_count += 1;
-        self.distance_count[self.graph.distances[u]] -= 1;
-
-        self.graph.distances[u] = self
-            .graph
-            .neighbors(u)
-            .filter(|edge| edge.residual_capacity() > 0 as Flow)
-            .map(|edge| self.graph.distances[edge.to] + 1)
-            .min()
-            .unwrap_or(self.graph.num_nodes())
-            .min(self.graph.num_nodes());
-
-        self.distance_count[self.graph.distances[u]] += 1;
+impl<F: Capacity> MaxFlowSolver<F> for BipartitePushRelabelHighestLabel<'_, F> {
+    fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        BipartitePushRelabelHighestLabel::solve(self, source, sink)
     }
 
-    // gap relabeling heuristic
-    fn gap_relabeling(&mut self, k: usize) {
-        for u in 0..self.graph.num_nodes() {
-            if self.graph.distances[u] >= k {
-                self.distance_count[self.graph.distances[u]] -= 1;
-                self.graph.distances[u] = self.graph.distances[u].max(self.graph.num_nodes());
-                self.distance_count[self.graph.distances[u]] += 1;
-            }
-        }
+    fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        BipartitePushRelabelHighestLabel::solve_multi(self, sources, sinks)
     }
 
-    fn push_flow_excess_back_to_source(&mut self, source: usize, sink: usize) {
-        for u in 0..self.graph.num_nodes() {
-            self.current_edge[u] = self.graph.start[u];
-        }
-
-        for u in 0..self.graph.num_nodes() {
-            if u == source || u == sink {
-                continue;
-            }
-            while self.graph.excesses[u] > 0 as Flow {
-                // u から source への逆辺を使ったパスをみつける
-                let mut visited = vec![false; self.graph.num_nodes()];
-                self.dfs(u, source, sink, self.graph.excesses[u], &mut visited);
-            }
-            assert_eq!(self.graph.excesses[u], 0 as Flow);
-        }
+    fn graph(&self) -> &Graph<F> {
+        self.graph
     }
 
-    fn dfs(&mut self, u: usize, source: usize, sink: usize, flow: Flow, visited: &mut Vec<bool>) -> Flow {
-        if u == source {
-            return flow;
-        }
-        visited[u] = true;
-
-        for i in self.current_edge[u]..self.graph.start[u + 1] {
-            self.current_edge[u] = i;
-            let to = self.graph.inside_edge_list[i].to;
-            let residual_capacity = self.graph.inside_edge_list[i].residual_capacity();
-            if visited[to] || residual_capacity == 0 as Flow {
-                continue;
-            }
-
-            let delta = self.dfs(to, source, sink, flow.min(residual_capacity), visited);
-            if delta > 0 as Flow {
-                self.graph.push_flow(u, i, delta);
-                return delta;
-            }
-        }
-        0 as Flow
+    fn graph_mut(&mut self) -> &mut Graph<F> {
+        self.graph
     }
 }
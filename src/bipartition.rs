@@ -0,0 +1,77 @@
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
+use crate::graph::Graph;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bipartition {
+    pub left: Vec<usize>,
+    pub right: Vec<usize>,
+}
+
+impl Bipartition {
+    pub fn num_left_nodes(&self) -> usize {
+        self.left.len()
+    }
+
+    pub fn num_right_nodes(&self) -> usize {
+        self.right.len()
+    }
+}
+
+// O(n + m)
+// two-colour the nodes other than source and sink, ignoring the direction of the edges
+// in every connected component the smaller colour class goes to the left side,
+// so that the left side is as small as possible
+pub fn detect_bipartition<F: Capacity>(graph: &Graph<F>, source: usize, sink: usize) -> Result<Bipartition, MaxFlowError> {
+    detect_bipartition_multi(graph, &[source], &[sink])
+}
+
+// O(n + m)
+// same as detect_bipartition, leaving out every source and sink
+pub fn detect_bipartition_multi<F: Capacity>(graph: &Graph<F>, sources: &[usize], sinks: &[usize]) -> Result<Bipartition, MaxFlowError> {
+    let n = graph.num_nodes();
+    graph.check_terminals(sources, sinks)?;
+    let mut is_terminal = vec![false; n];
+    sources.iter().chain(sinks).for_each(|&u| is_terminal[u] = true);
+
+    let mut adjacency: Vec<Vec<(usize, usize)>> = vec![Vec::new(); n];
+    for edge_index in 0..graph.num_edges() {
+        let e = graph.get_edge(edge_index);
+        if is_terminal[e.from] || is_terminal[e.to] {
+            continue;
+        }
+        adjacency[e.from].push((e.to, edge_index));
+        adjacency[e.to].push((e.from, edge_index));
+    }
+
+    let mut bipartition = Bipartition::default();
+    let mut colour = vec![usize::MAX; n];
+    let mut stack = Vec::new();
+    for root in 0..n {
+        if is_terminal[root] || colour[root] != usize::MAX {
+            continue;
+        }
+        let mut classes = [Vec::new(), Vec::new()];
+        colour[root] = 0;
+        stack.push(root);
+        while let Some(u) = stack.pop() {
+            classes[colour[u]].push(u);
+            for &(v, edge_index) in adjacency[u].iter() {
+                if colour[v] == usize::MAX {
+                    colour[v] = 1 - colour[u];
+                    stack.push(v);
+                } else if colour[v] == colour[u] {
+                    return Err(MaxFlowError::NotBipartite { edge_index });
+                }
+            }
+        }
+
+        let [a, b] = classes;
+        let (smaller, larger) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+        bipartition.left.extend(smaller);
+        bipartition.right.extend(larger);
+    }
+    bipartition.left.sort_unstable();
+    bipartition.right.sort_unstable();
+    Ok(bipartition)
+}
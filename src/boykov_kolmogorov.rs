@@ -0,0 +1,329 @@
+use std::collections::VecDeque;
+
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
+use crate::graph::{Flow, Graph};
+use crate::max_flow_solver::MaxFlowSolver;
+
+const TERMINAL: usize = usize::MAX; // parent of the roots
+const ORPHAN: usize = usize::MAX - 1; // parent of the orphans and the free nodes
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tree {
+    Free,
+    Source,
+    Sink,
+}
+
+// Boykov and Kolmogorov: a search tree grows from the sources and another one from the sinks,
+// a path is augmented where they touch, and the nodes cut off by saturated edges are adopted back instead of grown again
+// fast on the grid graphs of computer vision, see set_tweights
+pub struct BoykovKolmogorov<'a, F = Flow> {
+    pub graph: &'a mut Graph<F>,
+    terminals: Option<(usize, usize)>, // source and sink of set_tweights
+    tweights: Vec<[Option<usize>; 2]>, // by node, edge indices of source -> u and u -> sink
+
+    tree: Vec<Tree>,
+    parent: Vec<usize>, // inside edge index from u to its parent
+    active: VecDeque<usize>,
+    in_active: Vec<bool>,
+    current_edge: Vec<usize>, // next edge to scan of the active nodes, so that a terminal of large degree is scanned once
+    orphans: VecDeque<usize>,
+
+    // the distance to the root of nodes checked since the last augmentation, so that adoption prefers short paths
+    time: usize,
+    timestamps: Vec<usize>,
+    dists: Vec<usize>,
+}
+
+impl<'a, F: Capacity> BoykovKolmogorov<'a, F> {
+    pub fn new(graph: &'a mut Graph<F>) -> Result<Self, MaxFlowError> {
+        graph.build()?;
+        let n = graph.num_inside_nodes();
+        Ok(BoykovKolmogorov {
+            graph,
+            terminals: None,
+            tweights: Vec::new(),
+
+            tree: vec![Tree::Free; n],
+            parent: vec![ORPHAN; n],
+            active: VecDeque::new(),
+            in_active: vec![false; n],
+            current_edge: vec![0; n],
+            orphans: VecDeque::new(),
+
+            time: 0,
+            timestamps: vec![0; n],
+            dists: vec![0; n],
+        })
+    }
+
+    // the terminals that set_tweights connects the nodes to
+    pub fn with_terminals(graph: &'a mut Graph<F>, source: usize, sink: usize) -> Result<Self, MaxFlowError> {
+        graph.check_terminals(&[source], &[sink])?;
+        let mut solver = Self::new(graph)?;
+        solver.terminals = Some((source, sink));
+        Ok(solver)
+    }
+
+    // terminal weights of the graph cut libraries: edges source -> u of source_capacity and u -> sink of sink_capacity
+    // the edges are added by the first call for u and only their capacities change on the next ones, so the flow is kept
+    // a zero capacity adds no edge
+    pub fn set_tweights(&mut self, u: usize, source_capacity: F, sink_capacity: F) -> Result<(), MaxFlowError> {
+        let (source, sink) = self.terminals.ok_or(MaxFlowError::NoTerminals)?;
+        self.graph.check_node(u)?;
+        self.tweights.resize(self.graph.num_nodes(), [None, None]);
+        let edges = [(source, u, source_capacity), (u, sink, sink_capacity)];
+        for (k, (from, to, capacity)) in edges.into_iter().enumerate() {
+            match self.tweights[u][k] {
+                Some(edge_index) => self.graph.set_capacity(edge_index, capacity)?,
+                None => self.tweights[u][k] = self.graph.add_directed_edge(from, to, capacity),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        self.solve_multi(&[source], &[sink])
+    }
+
+    // O(m * n^2 * c) for a minimum cut of value c
+    // maximum flow from any of sources to any of sinks, the terminals are the roots of the two trees
+    pub fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        self.rebuild()?;
+        self.graph.check_terminals(sources, sinks)?;
+        if self.graph.num_edges() == 0 {
+            return Ok(F::zero());
+        }
+        self.graph.check_capacity_overflow(sources)?;
+        // warm start from the flow of the last solve
+        self.graph.repair_flow(sources, sinks);
+
+        self.init_trees(sources, sinks);
+        while let Some(i) = self.grow() {
+            self.time += 1;
+            self.augment(i);
+            self.adopt();
+        }
+
+        self.graph.update_excesses();
+        if self.graph.validation() {
+            self.graph.validate_flow(sources, sinks)?;
+        }
+        Ok(self.graph.flow_value(sinks))
+    }
+
+    // build again if edges or nodes were added through graph_mut
+    fn rebuild(&mut self) -> Result<(), MaxFlowError> {
+        self.graph.build()?;
+        let n = self.graph.num_inside_nodes();
+        self.tree.resize(n, Tree::Free);
+        self.parent.resize(n, ORPHAN);
+        self.in_active.resize(n, false);
+        self.current_edge.resize(n, 0);
+        self.timestamps.resize(n, 0);
+        self.dists.resize(n, 0);
+        Ok(())
+    }
+
+    fn init_trees(&mut self, sources: &[usize], sinks: &[usize]) {
+        self.tree.fill(Tree::Free);
+        self.parent.fill(ORPHAN);
+        self.in_active.fill(false);
+        self.active.clear();
+        self.orphans.clear();
+        self.time = 0;
+        self.timestamps.fill(0);
+        self.dists.fill(0);
+        for &s in sources {
+            self.tree[s] = Tree::Source;
+            self.parent[s] = TERMINAL;
+            self.activate(s);
+        }
+        for &t in sinks {
+            self.tree[t] = Tree::Sink;
+            self.parent[t] = TERMINAL;
+            self.activate(t);
+        }
+    }
+
+    fn activate(&mut self, u: usize) {
+        if !self.in_active[u] {
+            self.in_active[u] = true;
+            self.current_edge[u] = self.graph.start[u];
+            self.active.push_back(u);
+        }
+    }
+
+    // residual capacity from the parent of u to u in the source tree, or from u to its parent in the sink tree
+    #[inline]
+    fn tree_residual(&self, tree: Tree, i: usize) -> F {
+        match tree {
+            Tree::Source => self.graph.inside_edge_list[self.graph.inside_edge_list[i].rev].residual_capacity(),
+            _ => self.graph.inside_edge_list[i].residual_capacity(),
+        }
+    }
+
+    // grow the trees from the active nodes, return the inside edge from the source tree to the sink tree where they touch
+    // the active node stays at the front of the queue, so that the next growth continues from it
+    fn grow(&mut self) -> Option<usize> {
+        while let Some(&u) = self.active.front() {
+            let tree = self.tree[u];
+            // freed by adoption after it was activated
+            if tree == Tree::Free {
+                self.active.pop_front();
+                self.in_active[u] = false;
+                continue;
+            }
+            for i in self.current_edge[u]..self.graph.start[u + 1] {
+                self.current_edge[u] = i;
+                let e = &self.graph.inside_edge_list[i];
+                let (v, rev) = (e.to, e.rev);
+                // the direction of the flow, u -> v from the source tree and v -> u from the sink tree
+                let along = if tree == Tree::Source { i } else { rev };
+                if !self.graph.inside_edge_list[along].residual_capacity().is_positive() {
+                    continue;
+                }
+                match self.tree[v] {
+                    Tree::Free => {
+                        self.tree[v] = tree;
+                        self.parent[v] = rev;
+                        self.timestamps[v] = self.timestamps[u];
+                        self.dists[v] = self.dists[u] + 1;
+                        self.activate(v);
+                    }
+                    other if other != tree => return Some(along),
+                    _ => {}
+                }
+            }
+            self.active.pop_front();
+            self.in_active[u] = false;
+        }
+        None
+    }
+
+    // augment along source -> ... -> i -> ... -> sink, the nodes below a saturated tree edge become orphans
+    fn augment(&mut self, i: usize) {
+        let mut delta = self.graph.inside_edge_list[i].residual_capacity();
+        let x = self.graph.inside_edge_list[self.graph.inside_edge_list[i].rev].to;
+        let y = self.graph.inside_edge_list[i].to;
+        for (mut u, tree) in [(x, Tree::Source), (y, Tree::Sink)] {
+            while self.parent[u] != TERMINAL {
+                delta = delta.min_capacity(self.tree_residual(tree, self.parent[u]));
+                u = self.graph.inside_edge_list[self.parent[u]].to;
+            }
+        }
+
+        self.graph.augment(i, delta);
+        for (mut u, tree) in [(x, Tree::Source), (y, Tree::Sink)] {
+            while self.parent[u] != TERMINAL {
+                let a = self.parent[u];
+                let along = if tree == Tree::Source { self.graph.inside_edge_list[a].rev } else { a };
+                self.graph.augment(along, delta);
+                let next = self.graph.inside_edge_list[a].to;
+                if !self.graph.inside_edge_list[along].residual_capacity().is_positive() {
+                    self.parent[u] = ORPHAN;
+                    self.orphans.push_back(u);
+                }
+                u = next;
+            }
+        }
+    }
+
+    // an orphan takes a neighbor of its tree that still reaches a root as its parent, the one of least distance,
+    // or becomes free, and its children become orphans
+    fn adopt(&mut self) {
+        while let Some(x) = self.orphans.pop_front() {
+            let tree = self.tree[x];
+            let mut best: Option<(usize, usize)> = None;
+            for a in self.graph.start[x]..self.graph.start[x + 1] {
+                let y = self.graph.inside_edge_list[a].to;
+                if self.tree[y] != tree || !self.tree_residual(tree, a).is_positive() {
+                    continue;
+                }
+                if let Some(d) = self.root_distance(y) {
+                    if best.is_none_or(|(_, best_d)| d < best_d) {
+                        best = Some((a, d));
+                    }
+                }
+            }
+
+            if let Some((a, d)) = best {
+                self.parent[x] = a;
+                self.timestamps[x] = self.time;
+                self.dists[x] = d + 1;
+                continue;
+            }
+            for a in self.graph.start[x]..self.graph.start[x + 1] {
+                let y = self.graph.inside_edge_list[a].to;
+                if self.tree[y] != tree {
+                    continue;
+                }
+                // y can grow into x again, from its edge y -> x
+                if self.tree_residual(tree, a).is_positive() {
+                    self.activate(y);
+                    self.current_edge[y] = self.current_edge[y].min(self.graph.inside_edge_list[a].rev);
+                }
+                let p = self.parent[y];
+                if p != TERMINAL && p != ORPHAN && self.graph.inside_edge_list[p].to == x {
+                    self.parent[y] = ORPHAN;
+                    self.orphans.push_back(y);
+                }
+            }
+            self.tree[x] = Tree::Free;
+        }
+    }
+
+    // distance from u to its root, None if the path ends at an orphan
+    // the nodes on the path are stamped with the time, so that the next searches stop there
+    fn root_distance(&mut self, u: usize) -> Option<usize> {
+        let mut d = 0;
+        let mut v = u;
+        loop {
+            if self.timestamps[v] == self.time {
+                d += self.dists[v];
+                break;
+            }
+            match self.parent[v] {
+                TERMINAL => {
+                    self.timestamps[v] = self.time;
+                    self.dists[v] = 0;
+                    break;
+                }
+                ORPHAN => return None,
+                a => {
+                    d += 1;
+                    v = self.graph.inside_edge_list[a].to;
+                }
+            }
+        }
+
+        let mut dist = d;
+        let mut v = u;
+        while self.timestamps[v] != self.time {
+            self.timestamps[v] = self.time;
+            self.dists[v] = dist;
+            dist -= 1;
+            v = self.graph.inside_edge_list[self.parent[v]].to;
+        }
+        Some(d)
+    }
+}
+
+impl<F: Capacity> MaxFlowSolver<F> for BoykovKolmogorov<'_, F> {
+    fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        BoykovKolmogorov::solve(self, source, sink)
+    }
+
+    fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        BoykovKolmogorov::solve_multi(self, sources, sinks)
+    }
+
+    fn graph(&self) -> &Graph<F> {
+        self.graph
+    }
+
+    fn graph_mut(&mut self) -> &mut Graph<F> {
+        self.graph
+    }
+}
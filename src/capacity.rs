@@ -0,0 +1,141 @@
+use std::fmt::{Debug, Display};
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+// numeric type of capacities and flows
+pub trait Capacity: Copy + Debug + Display + Default + PartialOrd + Add<Output = Self> + Sub<Output = Self> + AddAssign + SubAssign {
+    fn zero() -> Self;
+
+    // used as infinity
+    fn max_value() -> Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+
+    // excesses can be negative for a while, which wraps around for unsigned types
+    fn wrapping_add(self, rhs: Self) -> Self;
+
+    fn wrapping_sub(self, rhs: Self) -> Self;
+
+    #[inline]
+    fn is_zero(self) -> bool {
+        self == Self::zero()
+    }
+
+    #[inline]
+    fn is_positive(self) -> bool {
+        self > Self::zero()
+    }
+
+    // self <= other, up to rounding errors
+    #[inline]
+    fn at_most(self, other: Self) -> bool {
+        self <= other
+    }
+
+    // self == other, up to rounding errors
+    #[inline]
+    fn approx_eq(self, other: Self) -> bool {
+        self == other
+    }
+
+    // not min, which would be ambiguous with Ord::min for the integer types once Capacity is in scope
+    #[inline]
+    fn min_capacity(self, other: Self) -> Self {
+        if other < self {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+macro_rules! impl_integer_capacity {
+    ($($t:ty),*) => {
+        $(
+            impl Capacity for $t {
+                #[inline]
+                fn zero() -> Self {
+                    0
+                }
+
+                #[inline]
+                fn max_value() -> Self {
+                    <$t>::MAX
+                }
+
+                #[inline]
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                #[inline]
+                fn wrapping_add(self, rhs: Self) -> Self {
+                    <$t>::wrapping_add(self, rhs)
+                }
+
+                #[inline]
+                fn wrapping_sub(self, rhs: Self) -> Self {
+                    <$t>::wrapping_sub(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_capacity!(i32, i64, i128, u32, u64, u128);
+
+// values within EPSILON of zero are treated as zero,
+// so that rounding errors do not leave residual arcs with a tiny capacity
+impl Capacity for f64 {
+    #[inline]
+    fn zero() -> Self {
+        0.0
+    }
+
+    #[inline]
+    fn max_value() -> Self {
+        f64::INFINITY
+    }
+
+    #[inline]
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        let sum = self + rhs;
+        if sum.is_finite() {
+            Some(sum)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn wrapping_add(self, rhs: Self) -> Self {
+        self + rhs
+    }
+
+    #[inline]
+    fn wrapping_sub(self, rhs: Self) -> Self {
+        self - rhs
+    }
+
+    #[inline]
+    fn is_zero(self) -> bool {
+        self.abs() <= EPSILON
+    }
+
+    #[inline]
+    fn is_positive(self) -> bool {
+        self > EPSILON
+    }
+
+    // sums of many flows carry a rounding error relative to their magnitude
+    #[inline]
+    fn at_most(self, other: Self) -> bool {
+        self <= other + EPSILON * self.abs().max(other.abs()).max(1.0)
+    }
+
+    #[inline]
+    fn approx_eq(self, other: Self) -> bool {
+        self.at_most(other) && other.at_most(self)
+    }
+}
+
+pub const EPSILON: f64 = 1e-9;
@@ -0,0 +1,345 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
+use crate::graph::{Flow, Graph};
+
+// DIMACS max flow format
+//   c <comment>
+//   p max <nodes> <arcs>
+//   n <id> s
+//   n <id> t
+//   a <from> <to> <capacity>
+// node ids are 1-based in the file and 0-based in the graph
+// the karz.py variant is also accepted: arc lines without "a" and a "c <n1> left nodes, <n2> right nodes" comment
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DimacsErrorKind {
+    MissingProblemLine,
+    DuplicateProblemLine,
+    UnsupportedProblem(String),
+    UnknownLineType(String),
+    MissingField(&'static str),
+    UnexpectedToken(String),
+    InvalidNumber(String),
+    InvalidTerminal(String),
+    NodeOutOfRange(usize),
+    NegativeCapacity(Flow),
+    SelfLoop(usize),
+    DuplicateSource,
+    DuplicateSink,
+    MissingSource,
+    MissingSink,
+    SourceIsSink,
+    ArcCountMismatch { expected: usize, found: usize },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DimacsError {
+    pub line: usize,   // 1-based
+    pub column: usize, // 1-based
+    pub kind: DimacsErrorKind,
+}
+
+impl fmt::Display for DimacsErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DimacsErrorKind::MissingProblemLine => write!(f, "missing problem line"),
+            DimacsErrorKind::DuplicateProblemLine => write!(f, "duplicate problem line"),
+            DimacsErrorKind::UnsupportedProblem(p) => write!(f, "unsupported problem type `{}`", p),
+            DimacsErrorKind::UnknownLineType(t) => write!(f, "unknown line type `{}`", t),
+            DimacsErrorKind::MissingField(field) => write!(f, "missing {}", field),
+            DimacsErrorKind::UnexpectedToken(t) => write!(f, "unexpected token `{}`", t),
+            DimacsErrorKind::InvalidNumber(t) => write!(f, "invalid number `{}`", t),
+            DimacsErrorKind::InvalidTerminal(t) => write!(f, "invalid terminal `{}`, expected `s` or `t`", t),
+            DimacsErrorKind::NodeOutOfRange(id) => write!(f, "node {} out of range", id),
+            DimacsErrorKind::NegativeCapacity(c) => write!(f, "negative capacity {}", c),
+            DimacsErrorKind::SelfLoop(id) => write!(f, "self loop on node {}", id),
+            DimacsErrorKind::DuplicateSource => write!(f, "duplicate source"),
+            DimacsErrorKind::DuplicateSink => write!(f, "duplicate sink"),
+            DimacsErrorKind::MissingSource => write!(f, "missing source"),
+            DimacsErrorKind::MissingSink => write!(f, "missing sink"),
+            DimacsErrorKind::SourceIsSink => write!(f, "source and sink are the same node"),
+            DimacsErrorKind::ArcCountMismatch { expected, found } => write!(f, "expected {} arcs, found {}", expected, found),
+        }
+    }
+}
+
+impl fmt::Display for DimacsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl Error for DimacsError {}
+
+pub struct DimacsProblem {
+    pub graph: Graph,
+    pub source: usize,
+    pub sink: usize,
+    pub bipartition: Option<(usize, usize)>, // (left nodes, right nodes) of the karz.py header
+    pub arc_edges: Vec<Option<usize>>,       // by arc in file order, its edge index, None for an arc of capacity 0 which has no edge
+}
+
+// tokens of a line with their 1-based columns
+struct Tokens<'a> {
+    line: usize,
+    tokens: Vec<(usize, &'a str)>,
+    pos: usize,
+    end_column: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(line: usize, text: &'a str) -> Self {
+        let mut tokens = Vec::new();
+        let mut begin = None;
+        for (i, c) in text.char_indices() {
+            match (c.is_whitespace(), begin) {
+                (false, None) => begin = Some(i),
+                (true, Some(b)) => {
+                    tokens.push((b, &text[b..i]));
+                    begin = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(b) = begin {
+            tokens.push((b, &text[b..]));
+        }
+        let tokens = tokens.into_iter().map(|(b, token)| (text[..b].chars().count() + 1, token)).collect();
+
+        Tokens {
+            line,
+            tokens,
+            pos: 0,
+            end_column: text.chars().count() + 1,
+        }
+    }
+
+    fn error(&self, column: usize, kind: DimacsErrorKind) -> DimacsError {
+        DimacsError {
+            line: self.line,
+            column,
+            kind,
+        }
+    }
+
+    fn next(&mut self, field: &'static str) -> Result<(usize, &'a str), DimacsError> {
+        match self.tokens.get(self.pos) {
+            Some(&token) => {
+                self.pos += 1;
+                Ok(token)
+            }
+            None => Err(self.error(self.end_column, DimacsErrorKind::MissingField(field))),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&mut self, field: &'static str) -> Result<(usize, T), DimacsError> {
+        let (column, token) = self.next(field)?;
+        match token.parse() {
+            Ok(value) => Ok((column, value)),
+            Err(_) => Err(self.error(column, DimacsErrorKind::InvalidNumber(token.to_string()))),
+        }
+    }
+
+    // 1-based id in the file to 0-based node
+    fn node(&mut self, field: &'static str, num_nodes: usize) -> Result<(usize, usize), DimacsError> {
+        let (column, id) = self.number::<usize>(field)?;
+        if id == 0 || id > num_nodes {
+            return Err(self.error(column, DimacsErrorKind::NodeOutOfRange(id)));
+        }
+        Ok((column, id - 1))
+    }
+
+    fn end(&self) -> Result<(), DimacsError> {
+        match self.tokens.get(self.pos) {
+            Some(&(column, token)) => Err(self.error(column, DimacsErrorKind::UnexpectedToken(token.to_string()))),
+            None => Ok(()),
+        }
+    }
+}
+
+// "c <n1> left nodes, <n2> right nodes"
+fn parse_bipartition(tokens: &Tokens) -> Option<(usize, usize)> {
+    match tokens.tokens[..] {
+        [(_, "c"), (_, left), (_, "left"), (_, "nodes,"), (_, right), (_, "right"), (_, "nodes")] => {
+            Some((left.parse().ok()?, right.parse().ok()?))
+        }
+        _ => None,
+    }
+}
+
+pub fn parse_dimacs(input: &str) -> Result<DimacsProblem, DimacsError> {
+    let mut problem: Option<(usize, usize, usize)> = None; // (nodes, arcs, line)
+    let mut source: Option<usize> = None;
+    let mut sink: Option<usize> = None;
+    let mut bipartition = None;
+    let mut graph = Graph::new();
+    let mut arc_edges = Vec::new();
+
+    for (i, text) in input.lines().enumerate() {
+        let mut tokens = Tokens::new(i + 1, text);
+        let (column, kind) = match tokens.tokens.first() {
+            Some(&token) => token,
+            None => continue,
+        };
+
+        if kind == "c" {
+            if bipartition.is_none() {
+                bipartition = parse_bipartition(&tokens);
+            }
+            continue;
+        }
+        if kind == "p" {
+            if problem.is_some() {
+                return Err(tokens.error(column, DimacsErrorKind::DuplicateProblemLine));
+            }
+            tokens.next("line type")?;
+            let (column, problem_type) = tokens.next("problem type")?;
+            if problem_type != "max" {
+                return Err(tokens.error(column, DimacsErrorKind::UnsupportedProblem(problem_type.to_string())));
+            }
+            let (_, num_nodes) = tokens.number("number of nodes")?;
+            let (_, arcs) = tokens.number("number of arcs")?;
+            tokens.end()?;
+            problem = Some((num_nodes, arcs, i + 1));
+            graph = Graph::with_num_nodes(num_nodes);
+            continue;
+        }
+
+        let num_nodes = match problem {
+            Some((num_nodes, _, _)) => num_nodes,
+            None => return Err(tokens.error(column, DimacsErrorKind::MissingProblemLine)),
+        };
+        match kind {
+            "n" => {
+                tokens.next("line type")?;
+                let (_, u) = tokens.node("node id", num_nodes)?;
+                let (column, terminal) = tokens.next("terminal")?;
+                tokens.end()?;
+                let (slot, duplicate) = match terminal {
+                    "s" => (&mut source, DimacsErrorKind::DuplicateSource),
+                    "t" => (&mut sink, DimacsErrorKind::DuplicateSink),
+                    _ => return Err(tokens.error(column, DimacsErrorKind::InvalidTerminal(terminal.to_string()))),
+                };
+                if slot.is_some() {
+                    return Err(tokens.error(column, duplicate));
+                }
+                *slot = Some(u);
+            }
+            _ => {
+                // karz.py writes arc lines without the leading "a"
+                if kind == "a" {
+                    tokens.next("line type")?;
+                } else if kind.parse::<usize>().is_err() {
+                    return Err(tokens.error(column, DimacsErrorKind::UnknownLineType(kind.to_string())));
+                }
+                let (_, from) = tokens.node("tail", num_nodes)?;
+                let (column, to) = tokens.node("head", num_nodes)?;
+                let (capacity_column, capacity) = tokens.number::<Flow>("capacity")?;
+                tokens.end()?;
+                if from == to {
+                    return Err(tokens.error(column, DimacsErrorKind::SelfLoop(to + 1)));
+                }
+                if capacity < 0 as Flow {
+                    return Err(tokens.error(capacity_column, DimacsErrorKind::NegativeCapacity(capacity)));
+                }
+                // add_directed_edge drops arcs of capacity 0, so the edge indices can run behind the arcs
+                arc_edges.push(graph.add_directed_edge(from, to, capacity));
+            }
+        }
+    }
+
+    let (_, arcs, line) = problem.ok_or(DimacsError {
+        line: input.lines().count() + 1,
+        column: 1,
+        kind: DimacsErrorKind::MissingProblemLine,
+    })?;
+    let error = |kind| DimacsError { line, column: 1, kind };
+    if arc_edges.len() != arcs {
+        return Err(error(DimacsErrorKind::ArcCountMismatch {
+            expected: arcs,
+            found: arc_edges.len(),
+        }));
+    }
+    let source = source.ok_or_else(|| error(DimacsErrorKind::MissingSource))?;
+    let sink = sink.ok_or_else(|| error(DimacsErrorKind::MissingSink))?;
+    if source == sink {
+        return Err(error(DimacsErrorKind::SourceIsSink));
+    }
+
+    Ok(DimacsProblem {
+        graph,
+        source,
+        sink,
+        bipartition,
+        arc_edges,
+    })
+}
+
+// edges written as arcs, the same for write_dimacs and write_dimacs_flow
+// DIMACS has neither node capacities nor lower bounds, which would change the max flow if left out
+fn written_edges<F: Capacity>(graph: &Graph<F>) -> io::Result<Vec<usize>> {
+    let unsupported = |e: MaxFlowError| io::Error::new(io::ErrorKind::InvalidInput, e);
+    if let Some(node) = (0..graph.num_nodes()).find(|&u| graph.node_capacity(u).is_some()) {
+        return Err(unsupported(MaxFlowError::NodeCapacityUnsupported { node }));
+    }
+    let edges: Vec<usize> = (0..graph.num_edges()).filter(|&edge_index| !graph.is_removed(edge_index)).collect();
+    if let Some(&edge_index) = edges.iter().find(|&&edge_index| graph.get_edge(edge_index).lower_bound.is_positive()) {
+        return Err(unsupported(MaxFlowError::LowerBoundUnsupported { edge_index }));
+    }
+    Ok(edges)
+}
+
+// an undirected edge is written as two arcs, the one from the smaller node first, so the max flow stays the same
+fn undirected_arcs<F: Capacity>(graph: &Graph<F>, edge_index: usize) -> [(usize, usize, F); 2] {
+    let e = graph.get_edge(edge_index);
+    let (u, v) = (e.from.min(e.to), e.from.max(e.to));
+    match e.from == u {
+        true => [(u, v, e.flow), (v, u, F::zero())],
+        false => [(u, v, F::zero()), (v, u, e.flow)],
+    }
+}
+
+// bipartition writes the karz.py header, so that the bipartite solvers can be run on the output
+// a removed edge is left out, and a graph with node capacities or lower bounds is an InvalidInput error
+pub fn write_dimacs<W: Write, F: Capacity>(w: &mut W, graph: &Graph<F>, source: usize, sink: usize, bipartition: Option<(usize, usize)>) -> io::Result<()> {
+    let edges = written_edges(graph)?;
+    if let Some((left, right)) = bipartition {
+        writeln!(w, "c {} left nodes, {} right nodes", left, right)?;
+    }
+    let num_arcs = edges.len() + edges.iter().filter(|&&edge_index| graph.is_undirected(edge_index)).count();
+    writeln!(w, "p max {} {}", graph.num_nodes(), num_arcs)?;
+    writeln!(w, "n {} s", source + 1)?;
+    writeln!(w, "n {} t", sink + 1)?;
+    for edge_index in edges {
+        let e = graph.get_edge(edge_index);
+        if graph.is_undirected(edge_index) {
+            for (from, to, _) in undirected_arcs(graph, edge_index) {
+                writeln!(w, "a {} {} {}", from + 1, to + 1, e.capacity)?;
+            }
+        } else {
+            writeln!(w, "a {} {} {}", e.from + 1, e.to + 1, e.capacity)?;
+        }
+    }
+    Ok(())
+}
+
+// DIMACS flow output, one "f" line per arc in the order of write_dimacs
+pub fn write_dimacs_flow<W: Write, F: Capacity>(w: &mut W, graph: &Graph<F>, value: F) -> io::Result<()> {
+    let edges = written_edges(graph)?;
+    writeln!(w, "s {}", value)?;
+    for edge_index in edges {
+        if graph.is_undirected(edge_index) {
+            for (from, to, flow) in undirected_arcs(graph, edge_index) {
+                writeln!(w, "f {} {} {}", from + 1, to + 1, flow)?;
+            }
+        } else {
+            let e = graph.get_edge(edge_index);
+            writeln!(w, "f {} {} {}", e.from + 1, e.to + 1, e.flow)?;
+        }
+    }
+    Ok(())
+}
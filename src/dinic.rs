@@ -1,50 +1,69 @@
 #![allow(dead_code)]
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
 use crate::graph::{Flow, Graph};
+use crate::max_flow_solver::MaxFlowSolver;
 
-pub struct Dinic<'a> {
-    pub graph: &'a mut Graph,
+pub struct Dinic<'a, F = Flow> {
+    pub graph: &'a mut Graph<F>,
     current_edge: Vec<usize>,
 }
 
-impl<'a> Dinic<'a> {
-    pub fn new(graph: &'a mut Graph) -> Self {
-        let n = graph.num_nodes();
-        Dinic {
+impl<'a, F: Capacity> Dinic<'a, F> {
+    pub fn new(graph: &'a mut Graph<F>) -> Result<Self, MaxFlowError> {
+        graph.build()?;
+        let n = graph.num_inside_nodes();
+        Ok(Dinic {
             graph,
             current_edge: vec![0; n],
-        }
+        })
+    }
+
+    pub fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        self.solve_multi(&[source], &[sink])
     }
 
-    pub fn solve(&mut self, source: usize, sink: usize) -> Flow {
-        self.graph.build();
-        if source == sink || self.graph.num_nodes() == 0 || self.graph.num_edges() == 0 {
-            return 0 as Flow;
+    // maximum flow from any of sources to any of sinks
+    pub fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        // build again if edges or nodes were added through graph_mut
+        self.graph.build()?;
+        self.current_edge.resize(self.graph.num_inside_nodes(), 0);
+        self.graph.check_terminals(sources, sinks)?;
+        if self.graph.num_edges() == 0 {
+            return Ok(F::zero());
         }
+        self.graph.check_capacity_overflow(sources)?;
+        // warm start from the flow of the last solve
+        self.graph.repair_flow(sources, sinks);
 
         loop {
-            self.graph.update_distance_to_sink(source, sink);
+            self.graph.update_distance_to_sink(sources, sinks);
 
             // no s-t path
-            if self.graph.distances[source] >= self.graph.num_nodes() {
+            if sources.iter().all(|&s| self.graph.distances[s] >= self.graph.num_inside_nodes()) {
                 break;
             }
 
             self.current_edge.iter_mut().enumerate().for_each(|(u, e)| *e = self.graph.start[u]);
-            match self.dfs(source, sink, Flow::MAX) {
-                Some(delta) => self.graph.excesses[sink] += delta,
-                None => break,
+            for &s in sources {
+                self.dfs(s, F::max_value());
             }
         }
 
-        self.graph.excesses[sink]
+        if self.graph.validation() {
+            self.graph.validate_flow(sources, sinks)?;
+        }
+        Ok(self.graph.flow_value(sinks))
     }
 
-    fn dfs(&mut self, u: usize, sink: usize, upper: Flow) -> Option<Flow> {
-        if u == sink {
+    // only the sinks have distance 0
+    fn dfs(&mut self, u: usize, upper: F) -> Option<F> {
+        if self.graph.distances[u] == 0 {
+            self.graph.excesses[u] += upper;
             return Some(upper);
         }
 
-        let mut res = 0 as Flow;
+        let mut res = F::zero();
         for i in self.current_edge[u]..self.graph.start[u + 1] {
             self.current_edge[u] = i;
             let v = self.graph.inside_edge_list[i].to;
@@ -55,15 +74,13 @@ impl<'a> Dinic<'a> {
                 continue;
             }
 
-            match self.dfs(v, sink, r.min(upper - res)) {
+            match self.dfs(v, r.min_capacity(upper - res)) {
                 Some(d) => {
                     // update flow
-                    let rev = self.graph.inside_edge_list[i].rev;
-                    self.graph.inside_edge_list[i].flow += d;
-                    self.graph.inside_edge_list[rev].flow -= d;
+                    self.graph.augment(i, d);
 
                     res += d;
-                    if res == upper {
+                    if (upper - res).is_zero() {
                         return Some(res);
                     }
                 }
@@ -71,8 +88,26 @@ impl<'a> Dinic<'a> {
             }
         }
         self.current_edge[u] = self.graph.start[u + 1];
-        self.graph.distances[u] = self.graph.num_nodes();
+        self.graph.distances[u] = self.graph.num_inside_nodes();
 
         Some(res)
     }
-}
\ No newline at end of file
+}
+
+impl<F: Capacity> MaxFlowSolver<F> for Dinic<'_, F> {
+    fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        Dinic::solve(self, source, sink)
+    }
+
+    fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        Dinic::solve_multi(self, sources, sinks)
+    }
+
+    fn graph(&self) -> &Graph<F> {
+        self.graph
+    }
+
+    fn graph_mut(&mut self) -> &mut Graph<F> {
+        self.graph
+    }
+}
@@ -0,0 +1,65 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MaxFlowError {
+    SelfLoop { edge_index: usize, node: usize },
+    NodeOutOfRange { node: usize, num_nodes: usize },
+    EdgeOutOfRange { edge_index: usize, num_edges: usize },
+    InvalidBipartition { num_left_nodes: usize, num_right_nodes: usize },
+    NotBipartite { edge_index: usize },
+    CapacityOverflow { node: usize },
+    NegativeCapacity { edge_index: usize },
+    NegativeNodeCapacity { node: usize },
+    NonMonotoneCapacity { edge_index: usize },
+    NegativeCostCycle { node: usize },
+    CostOverflow,
+    SourceIsSink { node: usize },
+    UndirectedEdgeAtSplitNode { edge_index: usize, node: usize },
+    NodeCapacityUnsupported { node: usize },
+    LowerBoundUnsupported { edge_index: usize },
+    AsymmetricCapacity { edge_index: usize },
+    EdgeRemoved { edge_index: usize },
+    NoTerminals,
+    NotBuilt,
+    // reported by the validation mode
+    CapacityViolation { edge_index: usize },
+    ConservationViolation { node: usize },
+}
+
+impl fmt::Display for MaxFlowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            MaxFlowError::SelfLoop { edge_index, node } => write!(f, "edge {} is a self loop on node {}", edge_index, node),
+            MaxFlowError::NodeOutOfRange { node, num_nodes } => write!(f, "node {} is out of range, the graph has {} nodes", node, num_nodes),
+            MaxFlowError::EdgeOutOfRange { edge_index, num_edges } => write!(f, "edge {} is out of range, the graph has {} edges", edge_index, num_edges),
+            MaxFlowError::InvalidBipartition {
+                num_left_nodes,
+                num_right_nodes,
+            } => write!(f, "invalid bipartition: {} left nodes, {} right nodes", num_left_nodes, num_right_nodes),
+            MaxFlowError::NotBipartite { edge_index } => write!(f, "edge {} closes an odd cycle, the graph is not bipartite", edge_index),
+            MaxFlowError::CapacityOverflow { node } => write!(f, "total capacity at node {} overflows the capacity type", node),
+            MaxFlowError::NegativeCapacity { edge_index } => write!(f, "negative capacity on edge {}", edge_index),
+            MaxFlowError::NegativeNodeCapacity { node } => write!(f, "negative capacity on node {}", node),
+            MaxFlowError::NonMonotoneCapacity { edge_index } => {
+                write!(f, "edge {} must leave source with a nonnegative slope or enter sink with a nonpositive slope", edge_index)
+            }
+            MaxFlowError::NegativeCostCycle { node } => write!(f, "the path from the source to node {} has a cycle of negative cost", node),
+            MaxFlowError::CostOverflow => write!(f, "total cost overflows"),
+            MaxFlowError::SourceIsSink { node } => write!(f, "source and sink are the same node {}", node),
+            MaxFlowError::UndirectedEdgeAtSplitNode { edge_index, node } => {
+                write!(f, "undirected edge {} touches node {}, which has a capacity", edge_index, node)
+            }
+            MaxFlowError::AsymmetricCapacity { edge_index } => write!(f, "edge {} has no reverse edge of the same capacity", edge_index),
+            MaxFlowError::NodeCapacityUnsupported { node } => write!(f, "capacity of node {} is not supported here", node),
+            MaxFlowError::LowerBoundUnsupported { edge_index } => write!(f, "lower bound of edge {} is not supported here", edge_index),
+            MaxFlowError::EdgeRemoved { edge_index } => write!(f, "edge {} was removed", edge_index),
+            MaxFlowError::NoTerminals => write!(f, "the terminals are not set"),
+            MaxFlowError::NotBuilt => write!(f, "the graph changed after the last solve, solve again first"),
+            MaxFlowError::CapacityViolation { edge_index } => write!(f, "flow on edge {} violates its capacity", edge_index),
+            MaxFlowError::ConservationViolation { node } => write!(f, "flow is not conserved at node {}", node),
+        }
+    }
+}
+
+impl Error for MaxFlowError {}
@@ -0,0 +1,157 @@
+use crate::capacity::Capacity;
+use crate::graph::{Flow, Graph};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowPath<F = Flow> {
+    pub edges: Vec<usize>, // edge indices in the order they are traversed
+    pub flow: F,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct FlowDecomposition<F = Flow> {
+    pub paths: Vec<FlowPath<F>>,  // source -> sink paths
+    pub cycles: Vec<FlowPath<F>>, // flow circulating without reaching sink
+}
+
+// O(n * m)
+// decompose the flow stored in graph into source -> sink paths and cycles
+// every path or cycle removes at least one edge from the flow, so at most m of them are returned
+pub fn decompose_flow<F: Capacity>(graph: &Graph<F>, source: usize, sink: usize) -> FlowDecomposition<F> {
+    let mut decomposer = FlowDecomposer::new(graph);
+    let mut decomposition = FlowDecomposition::default();
+
+    // once the cycles are removed, every walk from source ends at sink
+    decomposer.cancel_cycles(&mut decomposition);
+    if source != sink {
+        decomposer.find_paths(source, sink, &mut decomposition);
+    }
+    decomposition
+}
+
+struct FlowDecomposer<F> {
+    heads: Vec<usize>,
+    remaining: Vec<F>,
+    out_edges: Vec<Vec<usize>>, // edges carrying flow, by tail
+    current_edge: Vec<usize>,
+    finished: Vec<bool>,  // no cycle passes through the node
+    position: Vec<usize>, // position of the node on the dfs stack
+}
+
+impl<F: Capacity> FlowDecomposer<F> {
+    fn new(graph: &Graph<F>) -> Self {
+        let n = graph.num_nodes();
+        let mut heads = Vec::with_capacity(graph.num_edges());
+        let mut remaining = Vec::with_capacity(graph.num_edges());
+        let mut out_edges = vec![Vec::new(); n];
+        for edge_index in 0..graph.num_edges() {
+            let e = graph.get_edge(edge_index);
+            if e.flow.is_positive() {
+                out_edges[e.from].push(edge_index);
+            }
+            heads.push(e.to);
+            remaining.push(e.flow);
+        }
+
+        FlowDecomposer {
+            heads,
+            remaining,
+            out_edges,
+            current_edge: vec![0; n],
+            finished: vec![false; n],
+            position: vec![usize::MAX; n],
+        }
+    }
+
+    fn next_edge(&mut self, u: usize) -> Option<usize> {
+        while self.current_edge[u] < self.out_edges[u].len() {
+            let edge_index = self.out_edges[u][self.current_edge[u]];
+            if self.remaining[edge_index].is_positive() && !self.finished[self.heads[edge_index]] {
+                return Some(edge_index);
+            }
+            self.current_edge[u] += 1;
+        }
+        None
+    }
+
+    // dfs along the remaining flow, cancelling a cycle whenever a node on the stack is reached again
+    fn cancel_cycles(&mut self, decomposition: &mut FlowDecomposition<F>) {
+        for root in 0..self.out_edges.len() {
+            if self.finished[root] {
+                continue;
+            }
+            let mut nodes = vec![root];
+            let mut edges: Vec<usize> = Vec::new();
+            self.position[root] = 0;
+
+            while let Some(&u) = nodes.last() {
+                let edge_index = match self.next_edge(u) {
+                    Some(edge_index) => edge_index,
+                    None => {
+                        self.finished[u] = true;
+                        self.position[u] = usize::MAX;
+                        nodes.pop();
+                        edges.pop();
+                        continue;
+                    }
+                };
+                let v = self.heads[edge_index];
+                edges.push(edge_index);
+                if self.position[v] == usize::MAX {
+                    self.position[v] = nodes.len();
+                    nodes.push(v);
+                    continue;
+                }
+
+                // found a cycle v -> ... -> u -> v
+                let p = self.position[v];
+                let cycle = edges.split_off(p);
+                let flow = self.cancel(&cycle);
+                decomposition.cycles.push(FlowPath { edges: cycle, flow });
+                for &w in nodes[p + 1..].iter() {
+                    self.position[w] = usize::MAX;
+                }
+                nodes.truncate(p + 1);
+            }
+        }
+    }
+
+    fn find_paths(&mut self, source: usize, sink: usize, decomposition: &mut FlowDecomposition<F>) {
+        self.current_edge.fill(0);
+        self.finished.fill(false);
+
+        loop {
+            let mut u = source;
+            let mut edges = Vec::new();
+            while u != sink {
+                match self.next_edge(u) {
+                    Some(edge_index) => {
+                        edges.push(edge_index);
+                        u = self.heads[edge_index];
+                    }
+                    None => break,
+                }
+            }
+            // by flow conservation, only source can run out of flow
+            // with floating point capacities, other nodes can be left with a rounding error, which is dropped
+            if u != sink {
+                match edges.last() {
+                    Some(&edge_index) => {
+                        self.remaining[edge_index] = F::zero();
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+            let flow = self.cancel(&edges);
+            decomposition.paths.push(FlowPath { edges, flow });
+        }
+    }
+
+    fn cancel(&mut self, edges: &[usize]) -> F {
+        let flow = edges.iter().map(|&edge_index| self.remaining[edge_index]).reduce(F::min_capacity).unwrap_or(F::zero());
+        for &edge_index in edges.iter() {
+            self.remaining[edge_index] -= flow;
+        }
+        flow
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use crate::graph::{Flow, Graph};
+
+// rust port of the HiLo, Rope and ZipF generators in data/karz.py
+
+// splitmix64
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in [low, high]
+    pub fn gen_range(&mut self, low: u64, high: u64) -> u64 {
+        assert!(low <= high);
+        match (high - low).checked_add(1) {
+            Some(width) => low + self.next_u64() % width,
+            None => self.next_u64(),
+        }
+    }
+
+    // uniform in [0, 1)
+    pub fn gen_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn shuffle<T>(&mut self, v: &mut [T]) {
+        for i in (1..v.len()).rev() {
+            let j = self.gen_range(0, i as u64) as usize;
+            v.swap(i, j);
+        }
+    }
+
+    // k distinct values of [low, high]
+    pub fn sample(&mut self, low: usize, high: usize, k: usize) -> Vec<usize> {
+        let mut values: Vec<usize> = (low..=high).collect();
+        assert!(k <= values.len());
+        for i in 0..k {
+            let j = self.gen_range(i as u64, (values.len() - 1) as u64) as usize;
+            values.swap(i, j);
+        }
+        values.truncate(k);
+        values
+    }
+}
+
+// bipartite instance in the layout of karz.py
+// in edges and in the file, left nodes are 1..=n1, right nodes are n1+1..=n1+n2, source is n1+n2+1 and sink is n1+n2+2
+// source, sink and to_graph use the 0-based ids of parse_dimacs, one less
+pub struct BipartiteInstance {
+    pub num_left_nodes: usize,
+    pub num_right_nodes: usize,
+    pub edges: Vec<(usize, usize, Flow)>,
+}
+
+impl BipartiteInstance {
+    pub fn source(&self) -> usize {
+        self.num_left_nodes + self.num_right_nodes
+    }
+
+    pub fn sink(&self) -> usize {
+        self.num_left_nodes + self.num_right_nodes + 1
+    }
+
+    pub fn to_graph(&self) -> Graph {
+        let mut graph = Graph::with_num_nodes(self.num_left_nodes + self.num_right_nodes + 2);
+        for &(from, to, capacity) in self.edges.iter() {
+            graph.add_directed_edge(from - 1, to - 1, capacity);
+        }
+        graph
+    }
+
+    // same format as karz.py
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let (left, right) = (self.num_left_nodes, self.num_right_nodes);
+        writeln!(w, "c {} left nodes, {} right nodes", left, right)?;
+        writeln!(w, "p max {} {}", left + right + 2, self.edges.len())?;
+        writeln!(w, "n {} s", self.source() + 1)?;
+        writeln!(w, "n {} t", self.sink() + 1)?;
+        for &(from, to, capacity) in self.edges.iter() {
+            writeln!(w, "{} {} {}", from, to, capacity)?;
+        }
+        Ok(())
+    }
+}
+
+fn capacity(rng: &mut Random) -> Flow {
+    rng.gen_range(0, 1 << 24) as Flow
+}
+
+// edges are (left, right, capacity) with 0-based ids
+fn make_instance(
+    n1: usize,
+    n2: usize,
+    mut edges: Vec<(usize, usize, Flow)>,
+    capacity1: Vec<Flow>,
+    capacity2: Vec<Flow>,
+    rng: &mut Random,
+) -> BipartiteInstance {
+    let (s, t) = (n1 + n2 + 1, n1 + n2 + 2);
+    let mut perm1: Vec<usize> = (1..=n1).collect();
+    let mut perm2: Vec<usize> = (1..=n2).collect();
+    rng.shuffle(&mut perm1);
+    rng.shuffle(&mut perm2);
+    rng.shuffle(&mut edges);
+
+    let mut instance_edges = Vec::with_capacity(edges.len() + n1 + n2);
+    for &(v, u, f) in edges.iter() {
+        instance_edges.push((perm1[v], n1 + perm2[u], f));
+    }
+    for i in 0..n1 {
+        instance_edges.push((s, perm1[i], capacity1[i]));
+    }
+    for i in 0..n2 {
+        instance_edges.push((perm2[i] + n1, t, capacity2[i]));
+    }
+
+    BipartiteInstance {
+        num_left_nodes: n1,
+        num_right_nodes: n2,
+        edges: instance_edges,
+    }
+}
+
+// d edges go from the right side to the first side
+// None if n1 == 0
+pub fn hilo(n1: usize, n2: usize, d: usize, rng: &mut Random) -> Option<BipartiteInstance> {
+    if n1 == 0 {
+        return None;
+    }
+    let mut capacity1 = vec![0 as Flow; n1];
+    let mut capacity2 = vec![0 as Flow; n2];
+
+    let mut edges = Vec::new();
+    for (i, c2) in capacity2.iter_mut().enumerate() {
+        let mut j = i % n1;
+        let cap = capacity(rng);
+        capacity1[j] += cap;
+        *c2 = cap;
+        for _ in 0..d {
+            edges.push((j, i, cap));
+            if j == 0 {
+                break;
+            }
+            j -= 1;
+        }
+    }
+
+    Some(make_instance(n1, n2, edges, capacity1, capacity2, rng))
+}
+
+type Edges = Vec<(usize, usize, Flow)>;
+
+fn add_edges_max(v: (usize, usize), u: (usize, usize), edges: &mut Edges, capacity1: &mut [Flow], capacity2: &mut [Flow], rng: &mut Random) {
+    let mut j = v.0;
+    for (i, c2) in (u.0..=u.1).zip(capacity2[u.0..=u.1].iter_mut()) {
+        let cap = capacity(rng);
+        edges.push((j, i, cap));
+        capacity1[j] += cap;
+        *c2 += cap;
+        j += 1;
+        if j > v.1 {
+            j = v.0;
+        }
+    }
+}
+
+fn add_edges_random(v: (usize, usize), u: (usize, usize), edges: &mut Edges, rng: &mut Random) {
+    for i in u.0..=u.1 {
+        for j in rng.sample(v.0, v.1, v.1 - v.0) {
+            edges.push((j, i, capacity(rng)));
+        }
+    }
+}
+
+// n1 and n2 are rounded down to multiples of n1 / d, average degree d
+// None if d == 0, n1 < d or n2 < n1 / d
+pub fn rope(n1: usize, n2: usize, d: usize, rng: &mut Random) -> Option<BipartiteInstance> {
+    let t = n1.checked_div(d)?;
+    if t == 0 || n2 < t {
+        return None;
+    }
+    let d1 = n2 / t;
+    let n1 = d * t;
+    let n2 = d1 * t;
+
+    let mut edges = Vec::new();
+    let mut capacity1 = vec![0 as Flow; n1];
+    let mut capacity2 = vec![0 as Flow; n2];
+
+    for step in 0..t {
+        let (v1, v2, v3, v4) = (step * d, (step + 1) * d - 1, (step + 1) * d, (step + 2) * d - 1);
+        let (u1, u2, u3, u4) = (step * d1, (step + 1) * d1 - 1, (step + 1) * d1, (step + 2) * d1 - 1);
+        if step + 1 < t {
+            if step % 2 == 0 {
+                add_edges_max((v1, v2), (u3, u4), &mut edges, &mut capacity1, &mut capacity2, rng);
+                add_edges_max((v3, v4), (u1, u2), &mut edges, &mut capacity1, &mut capacity2, rng);
+            } else {
+                add_edges_random((v1, v2), (u3, u4), &mut edges, rng);
+                add_edges_random((v3, v4), (u1, u2), &mut edges, rng);
+            }
+        } else if step % 2 == 0 {
+            add_edges_max((v1, v2), (u1, u2), &mut edges, &mut capacity1, &mut capacity2, rng);
+        } else {
+            add_edges_random((v1, v2), (u1, u2), &mut edges, rng);
+        }
+    }
+
+    Some(make_instance(n1, n2, edges, capacity1, capacity2, rng))
+}
+
+// (n1 + n2) * d / 2 edges, edge i-j appears with probability proportional to 1 / (i * j)
+pub fn zipf(n1: usize, n2: usize, d: usize, rng: &mut Random) -> BipartiteInstance {
+    let mut sump = vec![0.0; n1.max(n2) + 1];
+    for i in 1..sump.len() {
+        sump[i] = sump[i - 1] + 1.0 / i as f64;
+    }
+
+    // pick a node in 1..=n with probability proportional to 1 / i
+    let pick = |n: usize, rng: &mut Random| {
+        let x = rng.gen_f64() * sump[n];
+        let (mut low, mut high) = (0, n);
+        while high - low > 1 {
+            let mid = (low + high) / 2;
+            if x > sump[mid] {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        high
+    };
+
+    let total_edges = (d * (n1 + n2) / 2).min(n1 * n2);
+    let mut edge_set = HashSet::new();
+    let mut edge_list = Vec::with_capacity(total_edges);
+    for _ in 0..total_edges {
+        loop {
+            let v = pick(n1, rng);
+            let u = pick(n2, rng);
+            if edge_set.insert((v, u)) {
+                edge_list.push((v, u));
+                break;
+            }
+        }
+    }
+
+    let mut capacity1 = vec![0 as Flow; n1];
+    let mut capacity2 = vec![0 as Flow; n2];
+    let mut edges = Vec::with_capacity(total_edges);
+    for &(v, u) in edge_list.iter() {
+        let cap = capacity(rng);
+        capacity1[v - 1] += cap;
+        capacity2[u - 1] += cap;
+        edges.push((v - 1, u - 1, cap));
+    }
+    for c in capacity1.iter_mut().chain(capacity2.iter_mut()) {
+        *c = rng.gen_range(0, *c as u64) as Flow;
+    }
+
+    make_instance(n1, n2, edges, capacity1, capacity2, rng)
+}
@@ -0,0 +1,118 @@
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
+use crate::graph::Flow;
+use crate::max_flow_solver::MaxFlowSolver;
+
+// Gomory-Hu tree of Gusfield, rooted at node 0
+// the minimum cut value between two nodes is the smallest value on the tree path between them,
+// and removing that tree edge splits the nodes into the two sides of a minimum cut
+#[derive(Clone, Debug, PartialEq)]
+pub struct GomoryHuTree<F = Flow> {
+    parent: Vec<usize>, // parent[0] = 0 for the root
+    values: Vec<F>,     // minimum cut value between u and parent[u]
+    depth: Vec<usize>,
+}
+
+impl<F: Capacity> GomoryHuTree<F> {
+    // O(n * max flow)
+    // n - 1 max flows with the solver, each one from a cleared graph, so any solver works
+    // the cut capacities must be symmetric: undirected edges, or directed edges in pairs of equal capacity, and no node capacity
+    pub fn new(solver: &mut dyn MaxFlowSolver<F>) -> Result<Self, MaxFlowError> {
+        solver.graph().check_symmetric()?;
+        let n = solver.graph().num_nodes();
+        let mut parent = vec![0; n];
+        let mut values = vec![F::zero(); n];
+        for s in 1..n {
+            let t = parent[s];
+            solver.graph_mut().clear();
+            let value = solver.solve(s, t)?;
+            let mut in_source_side = vec![false; n];
+            solver.min_cut(s)?.source_side.into_iter().for_each(|u| in_source_side[u] = true);
+
+            for u in 0..n {
+                if u != s && in_source_side[u] && parent[u] == t {
+                    parent[u] = s;
+                }
+            }
+            values[s] = value;
+            // s takes the place of t, so that every tree edge is a minimum cut and not only its value
+            if in_source_side[parent[t]] {
+                parent[s] = parent[t];
+                parent[t] = s;
+                values[s] = values[t];
+                values[t] = value;
+            }
+        }
+        solver.graph_mut().clear();
+
+        // the parents are not ordered by index after the swaps
+        let mut depth = vec![usize::MAX; n];
+        let mut path = Vec::new();
+        for u in 0..n {
+            let mut v = u;
+            while v != 0 && depth[v] == usize::MAX {
+                path.push(v);
+                v = parent[v];
+            }
+            let mut d = if v == 0 { 0 } else { depth[v] };
+            while let Some(w) = path.pop() {
+                d += 1;
+                depth[w] = d;
+            }
+        }
+        if n > 0 {
+            depth[0] = 0;
+        }
+        Ok(GomoryHuTree { parent, values, depth })
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.parent.len()
+    }
+
+    // (child, parent, minimum cut value) for every node except the root
+    pub fn edges(&self) -> Vec<(usize, usize, F)> {
+        (1..self.num_nodes()).map(|u| (u, self.parent[u], self.values[u])).collect()
+    }
+
+    // O(n)
+    // F::max_value() if u == v
+    pub fn min_cut_value(&self, u: usize, v: usize) -> F {
+        match self.lightest_edge(u, v) {
+            Some(w) => self.values[w],
+            None => F::max_value(),
+        }
+    }
+
+    // O(n)
+    // side of u of a minimum cut between u and v, empty if u == v
+    pub fn min_cut(&self, u: usize, v: usize) -> Vec<usize> {
+        let w = match self.lightest_edge(u, v) {
+            Some(w) => w,
+            None => return Vec::new(),
+        };
+        // the subtree of w is one side, parents come first in the order of depth
+        let mut in_subtree = vec![false; self.num_nodes()];
+        let mut order: Vec<usize> = (0..self.num_nodes()).collect();
+        order.sort_unstable_by_key(|&x| self.depth[x]);
+        for x in order {
+            in_subtree[x] = x == w || x != 0 && in_subtree[self.parent[x]];
+        }
+        (0..self.num_nodes()).filter(|&x| in_subtree[x] == in_subtree[u]).collect()
+    }
+
+    // child end of the tree edge of smallest value on the path between u and v
+    fn lightest_edge(&self, mut u: usize, mut v: usize) -> Option<usize> {
+        let mut lightest: Option<usize> = None;
+        while u != v {
+            if self.depth[u] < self.depth[v] {
+                std::mem::swap(&mut u, &mut v);
+            }
+            if lightest.is_none_or(|w| self.values[u] < self.values[w]) {
+                lightest = Some(u);
+            }
+            u = self.parent[u];
+        }
+        lightest
+    }
+}
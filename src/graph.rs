@@ -1,138 +1,626 @@
 #![allow(dead_code)]
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::Debug;
 
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
+
+// default capacity type
 pub type Flow = i64;
 
+// cost per unit of flow, see MinCostFlow
+pub type Cost = i64;
+
 #[derive(Debug, PartialEq)]
-pub struct Edge {
+pub struct Edge<F = Flow> {
     pub from: usize,
     pub to: usize,
-    pub flow: Flow,
-    pub capacity: Flow,
+    pub flow: F,
+    pub capacity: F,
+    pub cost: Cost,
+    pub lower_bound: F, // see max_flow_with_lower_bounds
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
-pub struct InsideEdge {
+pub struct InsideEdge<F = Flow> {
     pub to: usize,
-    pub flow: Flow,
-    pub capacity: Flow,
+    pub flow: F,
+    pub capacity: F,
     pub rev: usize,
 }
 
-impl InsideEdge {
+impl<F: Capacity> InsideEdge<F> {
     #[inline]
-    pub fn residual_capacity(&self) -> Flow {
-        assert!(self.flow <= self.capacity);
+    pub fn residual_capacity(&self) -> F {
+        debug_assert!(self.flow.at_most(self.capacity));
         self.capacity - self.flow
     }
 }
 
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MinCut {
+    pub source_side: Vec<usize>,
+    pub edges: Vec<usize>, // edge indices from the source side to the sink side, undirected edges in either direction
+    pub nodes: Vec<usize>, // nodes on the source side whose capacity is in the cut, see Graph::set_node_capacity
+}
+
 // CSR format
 #[derive(Default)]
-pub struct Graph {
+pub struct Graph<F = Flow> {
     num_nodes: usize,
     num_edges: usize,
-    tmp_edge_list: Vec<Edge>,
+    tmp_edge_list: Vec<Edge<F>>,
     edge_index_to_inside_edge_index: Vec<usize>,
     tails: Vec<usize>,
     build_done: bool,
+    validation: bool,
+    repairs: Vec<(usize, usize)>, // (tail, head) of the edges lowered below their flow by set_capacity
 
     pub start: Vec<usize>,
-    pub inside_edge_list: Vec<InsideEdge>,
+    pub inside_edge_list: Vec<InsideEdge<F>>,
+    costs: Vec<Cost>,          // by inside edge index, empty if every cost is zero
+    lower_bounds: Vec<F>,      // by edge index, empty if every lower bound is zero
+    undirected: Vec<Option<F>>, // by edge index, the capacity of undirected edges, empty if every edge is directed
+    node_capacities: Vec<Option<F>>, // by node, empty if no node has a capacity
+    split_nodes: Vec<usize>, // nodes with a capacity after build, the out copy of split_nodes[i] is num_nodes + i
+    removed: Vec<bool>,      // by edge index, empty if no edge was removed
 
-    pub excesses: Vec<Flow>,
+    pub excesses: Vec<F>,
     pub distances: Vec<usize>, // distance from u to sink in residual network
 
     que: VecDeque<usize>,
 }
 
-impl<'a> Graph {
+// state of Graph::recover_flow
+struct FlowWalk<F> {
+    carry: Vec<F>, // flow along each inside edge, zero on the edge against the flow
+    is_terminal: Vec<bool>,
+    current_edge: Vec<usize>, // next edge of u to look for a walk
+    path_index: Vec<usize>,   // position of u on the walk, usize::MAX if not on it
+}
+
+impl Graph {
+    // for other capacity types, use Graph::<F>::default()
     pub fn new() -> Self {
         Graph::default()
     }
+}
+
+impl<'a, F: Capacity> Graph<F> {
+    // nodes 0..num_nodes exist even if no edge touches them
+    pub fn with_num_nodes(num_nodes: usize) -> Self {
+        Graph {
+            num_nodes,
+            ..Graph::default()
+        }
+    }
+
+    // check the flow after every solve, see validate_flow
+    pub fn set_validation(&mut self, validation: bool) {
+        self.validation = validation;
+    }
+
+    #[inline]
+    pub fn validation(&self) -> bool {
+        self.validation
+    }
 
     #[inline]
     pub fn num_nodes(&self) -> usize {
         self.num_nodes
     }
 
+    // the nodes followed by the out copies of the split nodes, see set_node_capacity
+    // the solvers index their per node arrays by inside node
+    #[inline]
+    pub fn num_inside_nodes(&self) -> usize {
+        self.num_nodes + self.split_nodes.len()
+    }
+
     #[inline]
     pub fn num_edges(&self) -> usize {
         self.num_edges
     }
 
+    // the edges followed by the edges in copy -> out copy of the split nodes
+    #[inline]
+    pub(crate) fn num_edges_with_split(&self) -> usize {
+        self.num_edges + self.split_nodes.len()
+    }
+
+    // node of an inside node, the split node for an out copy
+    #[inline]
+    pub fn original_node(&self, u: usize) -> usize {
+        if u < self.num_nodes {
+            u
+        } else {
+            self.split_nodes[u - self.num_nodes]
+        }
+    }
+
     // return edge index
-    pub fn add_directed_edge(&mut self, from: usize, to: usize, capacity: Flow) -> Option<usize> {
-        if capacity <= 0 as Flow {
+    pub fn add_directed_edge(&mut self, from: usize, to: usize, capacity: F) -> Option<usize> {
+        self.add_directed_edge_with_cost(from, to, capacity, 0)
+    }
+
+    // the max flow solvers ignore the cost
+    // after a solve, the graph is taken apart and the next solve builds it again in O(n + m), see unbuild
+    pub fn add_directed_edge_with_cost(&mut self, from: usize, to: usize, capacity: F, cost: Cost) -> Option<usize> {
+        if !capacity.is_positive() {
             return None;
         }
+        self.unbuild();
         self.tmp_edge_list.push(Edge {
             from,
             to,
-            flow: 0 as Flow,
+            flow: F::zero(),
             capacity,
+            cost,
+            lower_bound: F::zero(),
         });
         self.num_nodes = self.num_nodes.max(from.max(to) + 1);
         self.num_edges += 1;
         Some(self.num_edges - 1)
     }
 
-    pub fn get_edge(&self, edge_index: usize) -> Edge {
-        let e = &self.inside_edge_list[self.edge_index_to_inside_edge_index[edge_index]];
+    // return the id of a new node without edges
+    // O(n + m) on the next solve as with add_directed_edge, since the out copies of the split nodes follow the nodes
+    pub fn add_node(&mut self) -> usize {
+        self.unbuild();
+        self.num_nodes += 1;
+        self.num_nodes - 1
+    }
+
+    // the edge keeps its index with capacity 0 and is left out of the cuts, its index is not reused
+    // its flow is moved away by the next solve, as with set_capacity
+    pub fn remove_edge(&mut self, edge_index: usize) -> Result<(), MaxFlowError> {
+        self.check_edge(edge_index)?;
+        self.set_capacity(edge_index, F::zero())?;
+        if let Some(lower) = self.lower_bounds.get_mut(edge_index) {
+            *lower = F::zero();
+        }
+        self.removed.resize(self.num_edges, false);
+        self.removed[edge_index] = true;
+        Ok(())
+    }
+
+    // O(m)
+    // remove every edge at u, the node keeps its id without edges
+    pub fn remove_node(&mut self, u: usize) -> Result<(), MaxFlowError> {
+        self.check_node(u)?;
+        for edge_index in 0..self.num_edges {
+            let e = self.get_edge(edge_index);
+            if (e.from == u || e.to == u) && !self.is_removed(edge_index) {
+                self.remove_edge(edge_index)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_removed(&self, edge_index: usize) -> bool {
+        self.removed.get(edge_index).copied().unwrap_or(false)
+    }
+
+    // the flow on the edge must be at least lower, see max_flow_with_lower_bounds
+    // the max flow solvers ignore the lower bound
+    pub fn add_directed_edge_with_bounds(&mut self, from: usize, to: usize, lower: F, upper: F) -> Option<usize> {
+        if lower < F::zero() || upper < lower {
+            return None;
+        }
+        let edge_index = self.add_directed_edge(from, to, upper)?;
+        if lower.is_positive() {
+            self.lower_bounds.resize(self.num_edges, F::zero());
+            self.lower_bounds[edge_index] = lower;
+        }
+        Some(edge_index)
+    }
+
+    // a single pair of inside edges, u -> v and v -> u both with capacity
+    // the inside edge u -> v has capacity 2 * capacity and its flow is capacity + the flow from u to v,
+    // so that the residual capacities are capacity - flow and capacity + flow
+    // also None if 2 * capacity overflows
+    pub fn add_undirected_edge(&mut self, u: usize, v: usize, capacity: F) -> Option<usize> {
+        capacity.checked_add(capacity)?;
+        let edge_index = self.add_directed_edge(u, v, capacity)?;
+        self.undirected.resize(self.num_edges, None);
+        self.undirected[edge_index] = Some(capacity);
+        Some(edge_index)
+    }
+
+    // at most capacity flows through u
+    // build splits u into an in copy, which keeps the id u and the edges entering u,
+    // and an out copy, which takes the edges leaving u, joined by an edge of the capacity
+    // so the capacity bounds the flow leaving u: a source sends at most capacity, and a sink absorbs any flow at its in copy
+    // node ids, edge indices and cuts stay those of the graph, the out copies are only seen by the solvers
+    // after build, a node that has no capacity yet is split by the next build, see unbuild
+    pub fn set_node_capacity(&mut self, u: usize, capacity: F) -> Result<(), MaxFlowError> {
+        if capacity < F::zero() {
+            return Err(MaxFlowError::NegativeNodeCapacity { node: u });
+        }
+        if let Ok(i) = self.split_nodes.binary_search(&u) {
+            self.node_capacities[u] = Some(capacity);
+            return self.set_capacity(self.num_edges + i, capacity);
+        }
+        self.unbuild();
+        self.num_nodes = self.num_nodes.max(u + 1);
+        self.node_capacities.resize(self.num_nodes, None);
+        self.node_capacities[u] = Some(capacity);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn node_capacity(&self, u: usize) -> Option<F> {
+        self.node_capacities.get(u).copied().flatten()
+    }
+
+    #[inline]
+    pub(crate) fn split_nodes(&self) -> &[usize] {
+        &self.split_nodes
+    }
+
+    #[inline]
+    pub fn is_undirected(&self, edge_index: usize) -> bool {
+        matches!(self.undirected.get(edge_index), Some(Some(_)))
+    }
+
+    // flow of the inside edge when no flow passes, the capacity of undirected edges and zero otherwise
+    #[inline]
+    fn offset(&self, edge_index: usize) -> F {
+        self.undirected.get(edge_index).copied().flatten().unwrap_or(F::zero())
+    }
+
+    // for an undirected edge, from and to follow the direction of the flow
+    pub fn get_edge(&self, edge_index: usize) -> Edge<F> {
+        let mut e = self.get_inside_edge(edge_index);
+        e.from = self.original_node(e.from);
+        e.to = self.original_node(e.to);
+        e
+    }
+
+    // same as get_edge with inside nodes, also for the edges of the split nodes after the edges
+    pub(crate) fn get_inside_edge(&self, edge_index: usize) -> Edge<F> {
+        let lower_bound = self.lower_bounds.get(edge_index).copied().unwrap_or(F::zero());
+        if !self.build_done {
+            let e = &self.tmp_edge_list[edge_index];
+            return Edge {
+                from: e.from,
+                to: e.to,
+                flow: e.flow,
+                capacity: e.capacity,
+                cost: e.cost,
+                lower_bound,
+            };
+        }
+        let inside_edge_index = self.edge_index_to_inside_edge_index[edge_index];
+        let e = &self.inside_edge_list[inside_edge_index];
+        let offset = self.offset(edge_index);
+        let (from, to, flow) = match e.flow < offset {
+            true => (e.to, self.tails[edge_index], offset - e.flow),
+            false => (self.tails[edge_index], e.to, e.flow - offset),
+        };
         Edge {
-            from: self.tails[edge_index],
-            to: e.to,
-            flow: e.flow,
-            capacity: e.capacity,
+            from,
+            to,
+            flow,
+            capacity: e.capacity - offset,
+            cost: self.cost(inside_edge_index),
+            lower_bound,
         }
     }
 
+    // cost of an inside edge, negated on reverse edges
+    #[inline]
+    pub fn cost(&self, inside_edge_index: usize) -> Cost {
+        self.costs.get(inside_edge_index).copied().unwrap_or(0)
+    }
+
+    // position of the edge in inside_edge_list, only after build
+    #[inline]
+    pub fn inside_edge_index(&self, edge_index: usize) -> usize {
+        self.edge_index_to_inside_edge_index[edge_index]
+    }
+
     pub fn clear(&mut self) {
-        for edge_index in 0..self.num_edges {
+        self.repairs.clear();
+        if !self.build_done {
+            self.tmp_edge_list.iter_mut().for_each(|e| e.flow = F::zero());
+            return;
+        }
+        for edge_index in 0..self.num_edges_with_split() {
             let inside_edge_index = self.edge_index_to_inside_edge_index[edge_index];
-            self.inside_edge_list[inside_edge_index].flow = 0;
+            let offset = self.offset(edge_index);
+            self.inside_edge_list[inside_edge_index].flow = offset;
             let rev = self.inside_edge_list[inside_edge_index].rev;
-            self.inside_edge_list[rev].flow = self.inside_edge_list[rev].capacity;
+            self.inside_edge_list[rev].flow = self.inside_edge_list[rev].capacity - offset;
         }
 
-        self.excesses.fill(0);
-        self.distances.fill(self.num_nodes);
+        let n = self.num_inside_nodes();
+        self.excesses.fill(F::zero());
+        self.distances.fill(n);
         self.que.clear();
     }
 
-    pub fn neighbors(&'a self, u: usize) -> std::slice::Iter<'a, InsideEdge> {
+    // the flow of the last solve is kept, so that the next solve starts from it
+    // lowering the capacity below the flow leaves an excess at the tail and a deficit at the head,
+    // which are moved away by repair_flow at the beginning of the next solve
+    pub fn set_capacity(&mut self, edge_index: usize, capacity: F) -> Result<(), MaxFlowError> {
+        self.check_edge(edge_index)?;
+        if capacity < F::zero() {
+            return Err(MaxFlowError::NegativeCapacity { edge_index });
+        }
+        if self.is_removed(edge_index) {
+            return Err(MaxFlowError::EdgeRemoved { edge_index });
+        }
+        let e = self.get_inside_edge(edge_index);
+        let offset = match self.is_undirected(edge_index) {
+            true => {
+                capacity.checked_add(capacity).ok_or(MaxFlowError::CapacityOverflow { node: e.from })?;
+                self.undirected[edge_index] = Some(capacity);
+                capacity
+            }
+            false => F::zero(),
+        };
+        if capacity < e.flow {
+            self.repairs.push((e.from, e.to));
+        }
+        if !self.build_done {
+            let e = &mut self.tmp_edge_list[edge_index];
+            e.capacity = capacity;
+            e.flow = e.flow.min_capacity(capacity);
+            return Ok(());
+        }
+
+        let i = self.edge_index_to_inside_edge_index[edge_index];
+        let rev = self.inside_edge_list[i].rev;
+        let flow = e.flow.min_capacity(capacity);
+        let inside_flow = if e.from == self.tails[edge_index] { offset + flow } else { offset - flow };
+        self.inside_edge_list[i].capacity = capacity + offset;
+        self.inside_edge_list[i].flow = inside_flow;
+        self.inside_edge_list[rev].capacity = capacity + offset;
+        self.inside_edge_list[rev].flow = capacity + offset - inside_flow;
+        Ok(())
+    }
+
+    // O(k * (n + m)) for k augmenting paths
+    // make the flow feasible again after set_capacity
+    // the surplus at the tail of a lowered edge is rerouted to its head if possible,
+    // and the rest is sent to another node with a deficit or to a terminal
+    pub fn repair_flow(&mut self, sources: &[usize], sinks: &[usize]) {
+        if self.repairs.is_empty() {
+            return;
+        }
+        let repairs = std::mem::take(&mut self.repairs);
+        let n = self.num_inside_nodes();
+        let mut is_terminal = vec![false; n];
+        sources.iter().chain(sinks).for_each(|&u| is_terminal[u] = true);
+
+        // only the ends of the lowered edges are unbalanced, and two lowered edges at a node may cancel out,
+        // so the imbalance is measured from the flows instead of taken from the repairs
+        // inflow and outflow are kept apart, since a deficit cannot be stored in an unsigned capacity type
+        let mut inflow = vec![F::zero(); n];
+        let mut outflow = vec![F::zero(); n];
+        for edge_index in 0..self.num_edges_with_split() {
+            let e = self.get_inside_edge(edge_index);
+            outflow[e.from] += e.flow;
+            inflow[e.to] += e.flow;
+        }
+        let mut unbalanced: Vec<usize> = repairs.iter().flat_map(|&(from, to)| [from, to]).filter(|&u| !is_terminal[u]).collect();
+        unbalanced.sort_unstable();
+        unbalanced.dedup();
+
+        for &(from, to) in repairs.iter() {
+            if is_terminal[from] && is_terminal[to] {
+                continue;
+            }
+            let supply = match (is_terminal[from], sources.contains(&from)) {
+                (false, _) => surplus(inflow[from], outflow[from]),
+                (true, true) => F::max_value(),
+                (true, false) => F::zero(),
+            };
+            let demand = match (is_terminal[to], sinks.contains(&to)) {
+                (false, _) => surplus(outflow[to], inflow[to]),
+                (true, true) => F::max_value(),
+                (true, false) => F::zero(),
+            };
+            let sent = self.route(from, to, supply.min_capacity(demand));
+            outflow[from] += sent;
+            inflow[to] += sent;
+        }
+
+        for &u in unbalanced.iter() {
+            for &v in unbalanced.iter() {
+                let amount = surplus(inflow[u], outflow[u]).min_capacity(surplus(outflow[v], inflow[v]));
+                if amount.is_positive() {
+                    let sent = self.route(u, v, amount);
+                    outflow[u] += sent;
+                    inflow[v] += sent;
+                }
+            }
+            for &t in sinks.iter().chain(sources) {
+                let amount = surplus(inflow[u], outflow[u]);
+                if !amount.is_positive() {
+                    break;
+                }
+                outflow[u] += self.route(u, t, amount);
+            }
+        }
+        for &u in unbalanced.iter() {
+            for &s in sources.iter().chain(sinks) {
+                let amount = surplus(outflow[u], inflow[u]);
+                if !amount.is_positive() {
+                    break;
+                }
+                inflow[u] += self.route(s, u, amount);
+            }
+        }
+
+        self.update_excesses();
+    }
+
+    // O(n + m)
+    // recompute the excesses from the flows
+    pub(crate) fn update_excesses(&mut self) {
+        self.excesses.fill(F::zero());
+        for edge_index in 0..self.num_edges_with_split() {
+            let e = self.get_inside_edge(edge_index);
+            self.excesses[e.from] = self.excesses[e.from].wrapping_sub(e.flow);
+            self.excesses[e.to] = self.excesses[e.to].wrapping_add(e.flow);
+        }
+    }
+
+    // only after build, the excesses are not updated
+    // from is the inside tail of the edge, or its head if the edge is undirected and the flow goes the other way
+    pub(crate) fn set_flow(&mut self, edge_index: usize, from: usize, flow: F) {
+        let i = self.edge_index_to_inside_edge_index[edge_index];
+        let rev = self.inside_edge_list[i].rev;
+        let offset = self.offset(edge_index);
+        let inside_flow = if from == self.tails[edge_index] { offset + flow } else { offset - flow };
+        debug_assert!(inside_flow.at_most(self.inside_edge_list[i].capacity));
+        self.inside_edge_list[i].flow = inside_flow;
+        self.inside_edge_list[rev].flow = self.inside_edge_list[rev].capacity - inside_flow;
+    }
+
+    // send up to amount from u to v along shortest augmenting paths, return the amount sent
+    fn route(&mut self, u: usize, v: usize, amount: F) -> F {
+        let mut sent = F::zero();
+        let mut parent = vec![usize::MAX; self.num_inside_nodes()]; // inside edge index
+        while (amount - sent).is_positive() {
+            parent.fill(usize::MAX);
+            self.que.clear();
+            self.que.push_back(u);
+            while let Some(w) = self.que.pop_front() {
+                if w == v {
+                    break;
+                }
+                for i in self.start[w]..self.start[w + 1] {
+                    let e = &self.inside_edge_list[i];
+                    if e.to != u && parent[e.to] == usize::MAX && e.residual_capacity().is_positive() {
+                        parent[e.to] = i;
+                        self.que.push_back(e.to);
+                    }
+                }
+            }
+            self.que.clear();
+            if parent[v] == usize::MAX {
+                break;
+            }
+
+            let mut delta = amount - sent;
+            let mut w = v;
+            while w != u {
+                let e = &self.inside_edge_list[parent[w]];
+                delta = delta.min_capacity(e.residual_capacity());
+                w = self.inside_edge_list[e.rev].to;
+            }
+            let mut w = v;
+            while w != u {
+                let i = parent[w];
+                self.augment(i, delta);
+                w = self.inside_edge_list[self.inside_edge_list[i].rev].to;
+            }
+            sent += delta;
+        }
+        sent
+    }
+
+    pub fn neighbors(&'a self, u: usize) -> std::slice::Iter<'a, InsideEdge<F>> {
         self.inside_edge_list[self.start[u]..self.start[u + 1]].iter()
     }
 
-    pub fn build(&mut self) {
-        if self.build_done {
+    // O(n + m)
+    // undo build, so that edges and nodes can be added, and the next build runs again with the flows kept
+    // the edges are taken back from the inside edges, an undirected edge in the direction of its flow,
+    // and the repairs in node ids, those of the split edges are found again by build
+    fn unbuild(&mut self) {
+        if !self.build_done {
             return;
         }
+        self.tmp_edge_list = (0..self.num_edges).map(|edge_index| self.get_edge(edge_index)).collect();
+        self.repairs = self.repairs.iter().map(|&(u, v)| (self.original_node(u), self.original_node(v))).filter(|&(u, v)| u != v).collect();
+        self.build_done = false;
+        self.split_nodes.clear();
+        self.start.clear();
+        self.inside_edge_list.clear();
+        self.edge_index_to_inside_edge_index.clear();
+        self.tails.clear();
+        self.costs.clear();
+        self.excesses.clear();
+        self.distances.clear();
+    }
+
+    // O(n + m)
+    // also after edges or nodes are added to a built graph, the solvers build again at the beginning of every solve
+    // the flows are kept: the flow through a split node is the flow leaving it, lowered to its capacity like set_capacity does,
+    // so a surplus or a deficit left at the node by set_capacity stays at its in copy
+    pub fn build(&mut self) -> Result<(), MaxFlowError> {
+        if self.build_done {
+            return Ok(());
+        }
+        if let Some(edge_index) = self.tmp_edge_list.iter().position(|e| e.from == e.to) {
+            let node = self.tmp_edge_list[edge_index].from;
+            return Err(MaxFlowError::SelfLoop { edge_index, node });
+        }
+        // an undirected edge would need both copies of a split node
+        for edge_index in 0..self.num_edges {
+            let e = &self.tmp_edge_list[edge_index];
+            if let Some(&node) = [e.from, e.to].iter().find(|&&u| self.is_undirected(edge_index) && self.node_capacity(u).is_some()) {
+                return Err(MaxFlowError::UndirectedEdgeAtSplitNode { edge_index, node });
+            }
+        }
         self.build_done = true;
+        self.split_nodes = (0..self.num_nodes).filter(|&u| self.node_capacity(u).is_some()).collect();
+        let mut out_copy: Vec<usize> = (0..self.num_nodes).collect();
+        for (i, &u) in self.split_nodes.iter().enumerate() {
+            out_copy[u] = self.num_nodes + i;
+        }
+        let mut outflow = vec![F::zero(); self.num_nodes];
+        for e in self.tmp_edge_list.iter_mut() {
+            outflow[e.from] += e.flow;
+            e.from = out_copy[e.from];
+        }
+        for &u in self.split_nodes.iter() {
+            let capacity = self.node_capacities[u].unwrap();
+            if capacity < outflow[u] {
+                self.repairs.push((u, out_copy[u]));
+            }
+            self.tmp_edge_list.push(Edge {
+                from: u,
+                to: out_copy[u],
+                flow: outflow[u].min_capacity(capacity),
+                capacity,
+                cost: 0,
+                lower_bound: F::zero(),
+            });
+        }
+        let (n, m) = (self.num_inside_nodes(), self.num_edges_with_split());
         // initialize
-        self.edge_index_to_inside_edge_index.resize(self.num_edges, usize::MAX);
-        self.tails.resize(self.num_edges, usize::MAX);
-        self.start.resize(self.num_nodes + 1, 0);
-        self.inside_edge_list.resize(2 * self.num_edges, InsideEdge::default());
-        self.excesses.resize(self.num_nodes, 0);
-        self.distances.resize(self.num_nodes, self.num_nodes);
-
-        let mut degree = vec![0; self.num_nodes];
+        self.edge_index_to_inside_edge_index.resize(m, usize::MAX);
+        self.tails.resize(m, usize::MAX);
+        self.start.resize(n + 1, 0);
+        self.inside_edge_list.resize(2 * m, InsideEdge::default());
+        self.excesses.resize(n, F::zero());
+        self.distances.resize(n, n);
+
+        let mut degree = vec![0; n];
         for e in self.tmp_edge_list.iter() {
             degree[e.to] += 1;
             degree[e.from] += 1;
         }
 
-        for i in 1..=self.num_nodes {
+        for i in 1..=n {
             self.start[i] += self.start[i - 1] + degree[i - 1];
         }
 
-        let mut counter = vec![0; self.num_nodes];
+        if self.tmp_edge_list.iter().any(|e| e.cost != 0) {
+            self.costs.resize(2 * m, 0);
+        }
+
+        let mut counter = vec![0; n];
         for (edge_index, e) in self.tmp_edge_list.iter().enumerate() {
             let (u, v) = (e.from, e.to);
-            assert_ne!(u, v);
+            let offset = self.offset(edge_index);
             let inside_edge_index_u = self.start[u] + counter[u];
             let inside_edge_index_v = self.start[v] + counter[v];
             counter[u] += 1;
@@ -141,69 +629,438 @@ impl<'a> Graph {
             // u -> v
             self.inside_edge_list[inside_edge_index_u] = InsideEdge {
                 to: v,
-                flow: 0,
-                capacity: e.capacity,
+                flow: offset + e.flow,
+                capacity: e.capacity + offset,
                 rev: inside_edge_index_v,
             };
             self.edge_index_to_inside_edge_index[edge_index] = inside_edge_index_u;
             self.tails[edge_index] = u;
+            if !self.costs.is_empty() {
+                self.costs[inside_edge_index_u] = e.cost;
+                self.costs[inside_edge_index_v] = -e.cost;
+            }
 
             // v -> u
             self.inside_edge_list[inside_edge_index_v] = InsideEdge {
                 to: u,
-                flow: e.capacity,
-                capacity: e.capacity,
+                flow: e.capacity - e.flow,
+                capacity: e.capacity + offset,
                 rev: inside_edge_index_u,
             };
         }
         self.tmp_edge_list.clear();
+        self.update_excesses();
+        Ok(())
+    }
+
+    pub fn check_edge(&self, edge_index: usize) -> Result<(), MaxFlowError> {
+        if edge_index >= self.num_edges {
+            return Err(MaxFlowError::EdgeOutOfRange {
+                edge_index,
+                num_edges: self.num_edges,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn check_node(&self, u: usize) -> Result<(), MaxFlowError> {
+        if u >= self.num_nodes {
+            return Err(MaxFlowError::NodeOutOfRange {
+                node: u,
+                num_nodes: self.num_nodes,
+            });
+        }
+        Ok(())
     }
 
-    pub fn push_flow(&mut self, u: usize, edge_index: usize, flow: Flow) {
-        if flow == 0 as Flow {
+    // common checks of the solvers before solving
+    // a node cannot be both a source and a sink
+    pub fn check_terminals(&self, sources: &[usize], sinks: &[usize]) -> Result<(), MaxFlowError> {
+        for &u in sources.iter().chain(sinks) {
+            self.check_node(u)?;
+        }
+        match sources.iter().find(|u| sinks.contains(u)) {
+            Some(&node) => Err(MaxFlowError::SourceIsSink { node }),
+            None => Ok(()),
+        }
+    }
+
+    // O(n + m)
+    // the cut capacities are symmetric, as Gomory-Hu and Stoer-Wagner need:
+    // the total capacity of the directed edges from u to v equals the one from v to u
+    // a node capacity only bounds the flow leaving the node when it is a terminal, see set_node_capacity
+    pub fn check_symmetric(&self) -> Result<(), MaxFlowError> {
+        if let Some(node) = (0..self.num_nodes).find(|&u| self.node_capacity(u).is_some()) {
+            return Err(MaxFlowError::NodeCapacityUnsupported { node });
+        }
+        let mut capacities: HashMap<(usize, usize), (F, F, usize)> = HashMap::new();
+        for edge_index in (0..self.num_edges).filter(|&edge_index| !self.is_undirected(edge_index)) {
+            let e = self.get_edge(edge_index);
+            let entry = capacities.entry((e.from.min(e.to), e.from.max(e.to))).or_insert((F::zero(), F::zero(), edge_index));
+            if e.from < e.to {
+                entry.0 += e.capacity;
+            } else {
+                entry.1 += e.capacity;
+            }
+        }
+        match capacities.values().find(|&&(forward, backward, _)| !forward.approx_eq(backward)) {
+            Some(&(_, _, edge_index)) => Err(MaxFlowError::AsymmetricCapacity { edge_index }),
+            None => Ok(()),
+        }
+    }
+
+    // O(n + m)
+    // every excess and the flow value are bounded by the total capacity of the edges leaving the sources,
+    // so the arithmetic of the solvers cannot overflow once this sum fits in F
+    pub fn check_capacity_overflow(&self, sources: &[usize]) -> Result<(), MaxFlowError> {
+        self.check_terminal_capacity(sources, true)
+    }
+
+    // O(n + m)
+    // same for the total capacity of the edges entering the sinks, which bounds the deficits of a pseudoflow
+    pub fn check_capacity_overflow_into(&self, sinks: &[usize]) -> Result<(), MaxFlowError> {
+        self.check_terminal_capacity(sinks, false)
+    }
+
+    // an edge in the other direction does not count, an undirected edge counts once, and an edge between two terminals not at all
+    fn check_terminal_capacity(&self, terminals: &[usize], leaving: bool) -> Result<(), MaxFlowError> {
+        let mut is_terminal = vec![false; self.num_inside_nodes()];
+        for &u in terminals {
+            is_terminal[u] = true;
+        }
+        let mut total = F::zero();
+        for edge_index in 0..self.num_edges_with_split() {
+            let inside_edge_index = self.edge_index_to_inside_edge_index[edge_index];
+            let (tail, head) = (self.tails[edge_index], self.inside_edge_list[inside_edge_index].to);
+            let (near, far) = if leaving { (tail, head) } else { (head, tail) };
+            let terminal = match self.is_undirected(edge_index) && is_terminal[far] {
+                true => far,
+                false => near,
+            };
+            if !is_terminal[terminal] || is_terminal[tail] && is_terminal[head] {
+                continue;
+            }
+            let capacity = self.inside_edge_list[inside_edge_index].capacity - self.offset(edge_index);
+            total = total.checked_add(capacity).ok_or(MaxFlowError::CapacityOverflow { node: self.original_node(terminal) })?;
+        }
+        Ok(())
+    }
+
+    // total excess of the sinks, the value of the flow
+    pub fn flow_value(&self, sinks: &[usize]) -> F {
+        sinks.iter().fold(F::zero(), |total, &t| total + self.excesses[t])
+    }
+
+    // O(n + m)
+    // check capacity constraints and flow conservation at every node except the sources and the sinks
+    // the capacity of a split node is checked on its edge in copy -> out copy, reported with an edge index after the edges
+    pub fn validate_flow(&self, sources: &[usize], sinks: &[usize]) -> Result<(), MaxFlowError> {
+        let n = self.num_inside_nodes();
+        let mut inflow = vec![F::zero(); n];
+        let mut outflow = vec![F::zero(); n];
+        for edge_index in 0..self.num_edges_with_split() {
+            let e = self.get_inside_edge(edge_index);
+            if !F::zero().at_most(e.flow) || !e.flow.at_most(e.capacity) {
+                return Err(MaxFlowError::CapacityViolation { edge_index });
+            }
+            outflow[e.from] = outflow[e.from].checked_add(e.flow).ok_or(MaxFlowError::CapacityOverflow { node: e.from })?;
+            inflow[e.to] = inflow[e.to].checked_add(e.flow).ok_or(MaxFlowError::CapacityOverflow { node: e.to })?;
+        }
+        for &u in sources.iter().chain(sinks) {
+            inflow[u] = outflow[u];
+        }
+        match (0..n).find(|&u| !inflow[u].approx_eq(outflow[u])) {
+            Some(node) => Err(MaxFlowError::ConservationViolation { node: self.original_node(node) }),
+            None => Ok(()),
+        }
+    }
+
+    // O(n * m)
+    // turn a preflow into a flow, the excess of every node other than the terminals goes back to the sources
+    pub(crate) fn push_excess_back_to_sources(&mut self, sources: &[usize], sinks: &[usize]) {
+        let mut excess = self.excesses.clone();
+        sources.iter().chain(sinks).for_each(|&u| excess[u] = F::zero());
+        let mut deficit = vec![F::zero(); self.num_inside_nodes()];
+        self.recover_flow(sources, sinks, &mut excess, &mut deficit);
+    }
+
+    // O(n * m)
+    // lower the flows until no node other than the terminals has an excess or a deficit, then update the excesses
+    // excess[u] goes back against the flow into u to a terminal or a node with a deficit, then deficit[u] along the flow out of u to a terminal
+    // the walks follow the flow rather than the residual network, so no flow is raised, and a walk that closes a cycle cancels it
+    // with floating point capacities, a rounding error may be left without a walk
+    pub(crate) fn recover_flow(&mut self, sources: &[usize], sinks: &[usize], excess: &mut [F], deficit: &mut [F]) {
+        let n = self.num_inside_nodes();
+        let mut walk = FlowWalk {
+            carry: vec![F::zero(); self.inside_edge_list.len()],
+            is_terminal: vec![false; n],
+            current_edge: self.start[..n].to_vec(),
+            path_index: vec![usize::MAX; n],
+        };
+        for edge_index in 0..self.num_edges_with_split() {
+            let e = self.get_inside_edge(edge_index);
+            let i = self.edge_index_to_inside_edge_index[edge_index];
+            let along = if self.inside_edge_list[i].to == e.to { i } else { self.inside_edge_list[i].rev };
+            walk.carry[along] = e.flow;
+        }
+        sources.iter().chain(sinks).for_each(|&u| walk.is_terminal[u] = true);
+
+        for u in 0..n {
+            while excess[u].is_positive() && self.cancel_flow(u, false, excess, deficit, &mut walk) {}
+        }
+        // the edges skipped against the flow may still carry flow along it
+        walk.current_edge.copy_from_slice(&self.start[..n]);
+        for u in 0..n {
+            while deficit[u].is_positive() && self.cancel_flow(u, true, excess, deficit, &mut walk) {}
+        }
+        self.update_excesses();
+    }
+
+    // walk from u against the flow, or along it with forward, and lower the flow on the path by as much as possible
+    // return false if the walk is stuck
+    fn cancel_flow(&mut self, u: usize, forward: bool, excess: &mut [F], deficit: &mut [F], walk: &mut FlowWalk<F>) -> bool {
+        let mut nodes = vec![u];
+        let mut path: Vec<usize> = Vec::new(); // path[k] carries flow between nodes[k] and nodes[k + 1]
+        walk.path_index[u] = 0;
+        let mut v = u;
+        while v == u || !walk.is_terminal[v] && (forward || !deficit[v].is_positive()) {
+            let next = (walk.current_edge[v]..self.start[v + 1])
+                .map(|i| (i, if forward { i } else { self.inside_edge_list[i].rev }))
+                .find(|&(_, along)| walk.carry[along].is_positive());
+            let Some((i, along)) = next else {
+                walk.current_edge[v] = self.start[v + 1];
+                nodes.iter().for_each(|&w| walk.path_index[w] = usize::MAX);
+                return false;
+            };
+            walk.current_edge[v] = i;
+            path.push(along);
+            let w = self.inside_edge_list[i].to;
+            if walk.path_index[w] == usize::MAX {
+                walk.path_index[w] = nodes.len();
+                nodes.push(w);
+                v = w;
+                continue;
+            }
+
+            // cancel the cycle back to w
+            let k = walk.path_index[w];
+            let delta = path[k..].iter().fold(walk.carry[along], |delta, &a| delta.min_capacity(walk.carry[a]));
+            for &a in path[k..].iter() {
+                walk.carry[a] -= delta;
+                self.augment(self.inside_edge_list[a].rev, delta);
+            }
+            nodes[k + 1..].iter().for_each(|&x| walk.path_index[x] = usize::MAX);
+            nodes.truncate(k + 1);
+            path.truncate(k);
+            v = w;
+        }
+
+        let mut delta = if forward { deficit[u] } else { excess[u] };
+        if !walk.is_terminal[v] {
+            delta = delta.min_capacity(deficit[v]);
+        }
+        delta = path.iter().fold(delta, |delta, &a| delta.min_capacity(walk.carry[a]));
+        for &a in path.iter() {
+            walk.carry[a] -= delta;
+            self.augment(self.inside_edge_list[a].rev, delta);
+        }
+        if !walk.is_terminal[v] {
+            deficit[v] -= delta;
+        }
+        if forward {
+            deficit[u] -= delta;
+        } else {
+            excess[u] -= delta;
+        }
+        nodes.iter().for_each(|&w| walk.path_index[w] = usize::MAX);
+        true
+    }
+
+    pub fn push_flow(&mut self, u: usize, edge_index: usize, flow: F) {
+        if flow.is_zero() {
             return;
         }
         let to = self.inside_edge_list[edge_index].to;
-        let rev = self.inside_edge_list[edge_index].rev;
-
-        // update flow
-        self.inside_edge_list[edge_index].flow += flow;
-        self.inside_edge_list[rev].flow -= flow;
+        self.augment(edge_index, flow);
 
         // update excess
-        self.excesses[u] -= flow;
-        self.excesses[to] += flow;
+        // the excess of source is negative, which wraps around for unsigned capacities
+        self.excesses[u] = self.excesses[u].wrapping_sub(flow);
+        self.excesses[to] = self.excesses[to].wrapping_add(flow);
+    }
 
-        assert!(self.inside_edge_list[edge_index].flow <= self.inside_edge_list[edge_index].capacity);
-        assert!(self.inside_edge_list[edge_index].flow >= 0 as Flow);
-        assert!(self.inside_edge_list[rev].flow <= self.inside_edge_list[rev].capacity);
-        assert!(self.inside_edge_list[rev].flow >= 0 as Flow);
+    // update the flow of an edge and its reverse edge, excesses are not touched
+    // the reverse flow is recomputed from the capacity, so that the two never drift apart with floating point capacities
+    #[inline]
+    pub fn augment(&mut self, edge_index: usize, flow: F) {
+        let rev = self.inside_edge_list[edge_index].rev;
+        let e = &mut self.inside_edge_list[edge_index];
+        debug_assert!(flow.at_most(e.residual_capacity()));
+        e.flow = (e.flow + flow).min_capacity(e.capacity);
+        let f = e.flow;
+        let r = &mut self.inside_edge_list[rev];
+        r.flow = r.capacity - f;
     }
 
     // O(n + m)
-    // calculate the distance from u to sink in the residual network
-    // if such a path does not exist, distance[u] becomes self.num_nodes
-    pub fn update_distance_to_sink(&mut self, source: usize, sink: usize) {
+    // calculate the distance from u to the nearest sink in the residual network
+    // if such a path does not exist, distance[u] becomes self.num_inside_nodes()
+    // only the sinks have distance 0, and paths through a source are not counted
+    pub fn update_distance_to_sink(&mut self, sources: &[usize], sinks: &[usize]) {
+        let n = self.num_inside_nodes();
         self.que.clear();
-        self.que.push_back(sink);
-        self.distances.fill(self.num_nodes);
-        self.distances[sink] = 0;
+        self.distances.fill(n);
+        for &t in sinks {
+            self.distances[t] = 0;
+            self.que.push_back(t);
+        }
+        // keep the sources out of the search
+        for &s in sources {
+            self.distances[s] = 0;
+        }
 
         while let Some(v) = self.que.pop_front() {
             for e in self.inside_edge_list[self.start[v]..self.start[v + 1]].iter() {
                 // e.to -> v
-                if e.flow > 0 as Flow && self.distances[e.to] > self.distances[v] + 1 {
+                if e.flow.is_positive() && self.distances[e.to] > self.distances[v] + 1 {
                     self.distances[e.to] = self.distances[v] + 1;
-                    if e.to != source {
-                        self.que.push_back(e.to);
-                    }
+                    self.que.push_back(e.to);
                 }
             }
         }
+
+        for &s in sources {
+            self.distances[s] = self
+                .neighbors(s)
+                .filter(|e| e.residual_capacity().is_positive() && !sources.contains(&e.to))
+                .map(|e| self.distances[e.to] + 1)
+                .min()
+                .unwrap_or(n)
+                .min(n);
+        }
     }
 
     #[inline]
     pub fn is_admissible_edge(&self, from: usize, i: usize) -> bool {
-        self.inside_edge_list[i].residual_capacity() > 0 && self.distances[from] == self.distances[self.inside_edge_list[i].to] + 1
+        self.inside_edge_list[i].residual_capacity().is_positive() && self.distances[from] == self.distances[self.inside_edge_list[i].to] + 1
+    }
+
+    // O(n + m)
+    // global update heuristic of the push relabel solvers: exact distances to sink with the sources at source_distance,
+    // and distance_count[d], the number of nodes of distance d, counted again, since a stale count would let
+    // the gap relabeling lift nodes that still reach sink
+    pub(crate) fn global_relabel(&mut self, sources: &[usize], sinks: &[usize], source_distance: usize, distance_count: &mut [usize]) {
+        self.update_distance_to_sink(sources, sinks);
+        for &s in sources {
+            self.distances[s] = source_distance;
+        }
+        self.count_distances(distance_count);
+    }
+
+    // O(n)
+    pub(crate) fn count_distances(&self, distance_count: &mut [usize]) {
+        distance_count.fill(0);
+        for u in 0..self.num_inside_nodes() {
+            distance_count[self.distances[u]] += 1;
+        }
+    }
+
+    // relabel u, which has no admissible edge left, to one more than its lowest neighbor in the residual network
+    // if u is the last node of its distance, the gap relabeling lifts it instead
+    // return whether u was relabeled alone, which the global update heuristic counts
+    pub(crate) fn relabel(&mut self, u: usize, distance_count: &mut [usize]) -> bool {
+        let n = self.num_inside_nodes();
+        if distance_count[self.distances[u]] == 1 {
+            self.gap_relabeling(self.distances[u], distance_count);
+            return false;
+        }
+        distance_count[self.distances[u]] -= 1;
+
+        let pre_distance = self.distances[u];
+        self.distances[u] = self
+            .neighbors(u)
+            .filter(|edge| edge.residual_capacity().is_positive())
+            .map(|edge| self.distances[edge.to] + 1)
+            .min()
+            .unwrap_or(n)
+            .min(n);
+
+        debug_assert!(self.distances[u] >= pre_distance);
+        distance_count[self.distances[u]] += 1;
+        true
+    }
+
+    // gap relabeling heuristic
+    // no node is left with distance k, so the nodes above cannot reach sink: set distance[u] >= k to distance[u] = n
+    // O(n)
+    fn gap_relabeling(&mut self, k: usize, distance_count: &mut [usize]) {
+        let n = self.num_inside_nodes();
+        for u in 0..n {
+            if self.distances[u] >= k {
+                distance_count[self.distances[u]] -= 1;
+                self.distances[u] = self.distances[u].max(n);
+                distance_count[self.distances[u]] += 1;
+            }
+        }
+    }
+
+    // O(n + m)
+    // the nodes reachable from source in the residual network form the source side of a minimum cut
+    // valid once a maximum flow is stored in the graph, NotBuilt if edges or nodes were added or removed since the last solve
+    pub fn min_cut(&self, source: usize) -> Result<MinCut, MaxFlowError> {
+        self.min_cut_multi(&[source])
+    }
+
+    // O(n + m)
+    // same as min_cut, with every source on the source side
+    // an undirected edge is in the cut if its ends are on different sides
+    pub fn min_cut_multi(&self, sources: &[usize]) -> Result<MinCut, MaxFlowError> {
+        if !self.build_done {
+            return Err(MaxFlowError::NotBuilt);
+        }
+        for &s in sources {
+            self.check_node(s)?;
+        }
+        let mut visited = vec![false; self.num_inside_nodes()];
+        let mut stack = sources.to_vec();
+        sources.iter().for_each(|&s| visited[s] = true);
+        while let Some(u) = stack.pop() {
+            for e in self.neighbors(u) {
+                if e.residual_capacity().is_positive() && !visited[e.to] {
+                    visited[e.to] = true;
+                    stack.push(e.to);
+                }
+            }
+        }
+        Ok(self.cut(&visited))
+    }
+
+    // cut between the inside nodes on the source side and the others, in node ids and edge indices
+    // a split node is on the source side with its in copy, and its capacity is in the cut when its out copy is not
+    pub(crate) fn cut(&self, in_source_side: &[bool]) -> MinCut {
+        let source_side = (0..self.num_nodes).filter(|&u| in_source_side[u]).collect();
+        let edges = (0..self.num_edges)
+            .filter(|&edge_index| !self.is_removed(edge_index))
+            .filter(|&edge_index| {
+                let e = &self.inside_edge_list[self.edge_index_to_inside_edge_index[edge_index]];
+                let (u, v) = (self.tails[edge_index], e.to);
+                in_source_side[u] && !in_source_side[v] || self.is_undirected(edge_index) && in_source_side[v] && !in_source_side[u]
+            })
+            .collect();
+        let nodes = (0..self.split_nodes.len())
+            .filter(|&i| in_source_side[self.split_nodes[i]] && !in_source_side[self.num_nodes + i])
+            .map(|i| self.split_nodes[i])
+            .collect();
+        MinCut { source_side, edges, nodes }
+    }
+}
+
+// a - b if positive, 0 otherwise
+#[inline]
+pub(crate) fn surplus<F: Capacity>(a: F, b: F) -> F {
+    if a > b {
+        a - b
+    } else {
+        F::zero()
     }
 }
@@ -0,0 +1,41 @@
+pub mod bipartite_matching;
+pub mod bipartite_push_relabel_fifo;
+pub mod bipartite_push_relabel_highest_label;
+pub mod bipartition;
+pub mod boykov_kolmogorov;
+pub mod capacity;
+pub mod dimacs;
+pub mod dinic;
+pub mod error;
+pub mod flow_decomposition;
+pub mod generators;
+pub mod gomory_hu;
+pub mod graph;
+pub mod lower_bounds;
+pub mod max_flow_solver;
+pub mod min_cost_flow;
+pub mod parametric;
+pub mod pseudoflow;
+pub mod push_relabel_fifo;
+pub mod push_relabel_highest_label;
+pub mod stoer_wagner;
+
+pub use bipartite_matching::BipartiteMatching;
+pub use bipartite_push_relabel_fifo::BipartitePushRelabelFIFO;
+pub use bipartite_push_relabel_highest_label::BipartitePushRelabelHighestLabel;
+pub use bipartition::{detect_bipartition, detect_bipartition_multi, Bipartition};
+pub use boykov_kolmogorov::BoykovKolmogorov;
+pub use capacity::Capacity;
+pub use dinic::Dinic;
+pub use error::MaxFlowError;
+pub use flow_decomposition::{decompose_flow, FlowDecomposition, FlowPath};
+pub use gomory_hu::GomoryHuTree;
+pub use graph::{Cost, Edge, Flow, Graph, MinCut};
+pub use lower_bounds::{max_flow_with_lower_bounds, Feasibility};
+pub use max_flow_solver::MaxFlowSolver;
+pub use min_cost_flow::MinCostFlow;
+pub use parametric::ParametricMaxFlow;
+pub use pseudoflow::Pseudoflow;
+pub use push_relabel_fifo::PushRelabelFIFO;
+pub use push_relabel_highest_label::PushRelabelHighestLabel;
+pub use stoer_wagner::stoer_wagner;
@@ -0,0 +1,83 @@
+use crate::capacity::Capacity;
+use crate::dinic::Dinic;
+use crate::error::MaxFlowError;
+use crate::graph::{Flow, Graph, MinCut};
+use crate::max_flow_solver::MaxFlowSolver;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Feasibility<F = Flow> {
+    // value of the maximum flow, which is stored in the graph
+    Feasible(F),
+    // certificate of infeasibility:
+    // the lower bounds of the edges entering source_side exceed the capacities of cut.edges and cut.nodes
+    Infeasible(MinCut),
+}
+
+// O(Dinic)
+// maximum flow from source to sink where every edge carries at least its lower bound, see Graph::add_directed_edge_with_bounds
+// reduction to a flow without lower bounds on an auxiliary graph:
+// edges get capacity upper - lower, the lower bounds become supplies and demands of the nodes,
+// which are met from a super source and a super sink, and an edge sink -> source of infinite capacity closes the circulation
+// once all supplies are met, that edge is removed and the flow is augmented from source to sink
+// the auxiliary graph is built on the inside nodes, so that the edges of the split nodes are kept
+// the value of the flow is never negative: if the lower bounds force a net flow from sink to source, the result is infeasible
+pub fn max_flow_with_lower_bounds<F: Capacity>(graph: &mut Graph<F>, source: usize, sink: usize) -> Result<Feasibility<F>, MaxFlowError> {
+    graph.build()?;
+    graph.check_terminals(&[source], &[sink])?;
+    let n = graph.num_inside_nodes();
+    let (super_source, super_sink) = (n, n + 1);
+
+    // inflow - outflow of the lower bounds, kept apart since unsigned capacities cannot be negative
+    let mut lower_in = vec![F::zero(); n];
+    let mut lower_out = vec![F::zero(); n];
+    let mut auxiliary = Graph::<F>::with_num_nodes(n + 2);
+    let mut auxiliary_edge = Vec::with_capacity(graph.num_edges_with_split());
+    for edge_index in 0..graph.num_edges_with_split() {
+        let e = graph.get_inside_edge(edge_index);
+        if e.capacity < e.lower_bound {
+            return Err(MaxFlowError::NegativeCapacity { edge_index });
+        }
+        lower_in[e.to] = lower_in[e.to].checked_add(e.lower_bound).ok_or(MaxFlowError::CapacityOverflow { node: e.to })?;
+        lower_out[e.from] = lower_out[e.from].checked_add(e.lower_bound).ok_or(MaxFlowError::CapacityOverflow { node: e.from })?;
+        auxiliary_edge.push(match graph.is_undirected(edge_index) {
+            true => auxiliary.add_undirected_edge(e.from, e.to, e.capacity),
+            false => auxiliary.add_directed_edge(e.from, e.to, e.capacity - e.lower_bound),
+        });
+    }
+    let circulation_edge = auxiliary.add_directed_edge(sink, source, F::max_value()).unwrap();
+    let mut demand = F::zero();
+    for u in 0..n {
+        if lower_in[u] > lower_out[u] {
+            auxiliary.add_directed_edge(super_source, u, lower_in[u] - lower_out[u]);
+            demand = demand.checked_add(lower_in[u] - lower_out[u]).ok_or(MaxFlowError::CapacityOverflow { node: u })?;
+        } else if lower_out[u] > lower_in[u] {
+            auxiliary.add_directed_edge(u, super_sink, lower_out[u] - lower_in[u]);
+        }
+    }
+
+    let mut solver = Dinic::new(&mut auxiliary)?;
+    let supplied = solver.solve(super_source, super_sink)?;
+    if !demand.at_most(supplied) {
+        let mut in_source_side = vec![false; n];
+        solver.min_cut(super_source)?.source_side.into_iter().filter(|&u| u < n).for_each(|u| in_source_side[u] = true);
+        return Ok(Feasibility::Infeasible(graph.cut(&in_source_side)));
+    }
+
+    // the super source and the super sink cannot be on an augmenting path, since their edges are saturated
+    solver.set_capacity(circulation_edge, F::zero())?;
+    solver.solve(source, sink)?;
+
+    for (edge_index, aux_index) in auxiliary_edge.into_iter().enumerate() {
+        let e = graph.get_inside_edge(edge_index);
+        let (from, flow) = aux_index.map_or((e.from, F::zero()), |i| (solver.get_edge(i).from, solver.get_edge(i).flow));
+        graph.set_flow(edge_index, from, e.lower_bound + flow);
+    }
+    graph.update_excesses();
+    if graph.validation() {
+        graph.validate_flow(&[source], &[sink])?;
+        if let Some(edge_index) = (0..graph.num_edges()).find(|&i| !graph.get_edge(i).lower_bound.at_most(graph.get_edge(i).flow)) {
+            return Err(MaxFlowError::CapacityViolation { edge_index });
+        }
+    }
+    Ok(Feasibility::Feasible(graph.excesses[sink]))
+}
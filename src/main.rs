@@ -1,67 +1,122 @@
 use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::process;
 use std::time::Instant;
 
-use bipartite_push_relabel_fifo::BipartitePushRelabelFIFO;
-use dinic::Dinic;
-use bipartite_push_relabel_highest_label::BipartitePushRelabelHighestLabel;
-use graph::Graph;
+use maxflow::dimacs::{parse_dimacs, DimacsProblem};
+use maxflow::generators::{self, Random};
+use maxflow::{detect_bipartition, BipartitePushRelabelFIFO, BipartitePushRelabelHighestLabel, Dinic, Graph, MaxFlowError, MaxFlowSolver, Pseudoflow};
 
-mod graph;
-mod bipartite_push_relabel_fifo;
-mod bipartite_push_relabel_highest_label;
-mod dinic;
+const USAGE: &str = "usage: maxflow <file> [alpha]\n       maxflow generate <dir> [seed]";
 
 fn main() {
     let args: Vec<String> = env::args().collect();
+    if args.len() < 2 || args[1] == "generate" && args.len() < 3 {
+        exit_with(USAGE);
+    }
+    if args[1] == "generate" {
+        let seed = args.get(3).map_or(0, |seed| seed.parse().unwrap_or_else(|e| exit_with(&format!("seed {}: {}", seed, e))));
+        generate(&args[2], seed);
+        return;
+    }
     let file_path = &args[1];
+    // global update of the push relabel solvers after every alpha * n relabels, none by default
+    let alpha = args.get(2).map_or(0, |alpha| alpha.parse().unwrap_or_else(|e| exit_with(&format!("alpha {}: {}", alpha, e))));
 
-    let mut graph = Graph::new();
-    let data = std::fs::read_to_string(file_path).unwrap();
-    let data: Vec<&str> = data.trim().split('\n').collect();
-
-    let (num_left_nodes, num_right_nodes) = {
-        let header: Vec<&str> = data[0].trim().split(' ').collect();
-        let left_nodes: usize = header[1].parse().unwrap();
-        let right_nodes: usize = header[4].parse().unwrap();
-        (left_nodes, right_nodes)
+    let data = std::fs::read_to_string(file_path).unwrap_or_else(|e| exit_with(&format!("{}: {}", file_path, e)));
+    let DimacsProblem {
+        mut graph,
+        source,
+        sink,
+        bipartition,
+        ..
+    } = match parse_dimacs(&data) {
+        Ok(problem) => problem,
+        Err(e) => exit_with(&format!("{}: {}", file_path, e)),
     };
-    let source = {
-        let header: Vec<&str> = data[2].trim().split(' ').collect();
-        let a: usize = header[1].parse().unwrap();
-        a
-    };
-    let sink = {
-        let header: Vec<&str> = data[3].trim().split(' ').collect();
-        let a: usize = header[1].parse().unwrap();
-        a
+    // without the karz.py header, the sides are detected from the graph
+    let (num_left_nodes, num_right_nodes) = match bipartition {
+        Some(bipartition) => bipartition,
+        None => match detect_bipartition(&graph, source, sink) {
+            Ok(bipartition) => (bipartition.num_left_nodes(), bipartition.num_right_nodes()),
+            Err(e) => exit_with(&format!("{}: {}", file_path, e)),
+        },
     };
 
-    for line in data[4..].iter() {
-        let line: Vec<&str> = line.trim().split(' ').collect();
-        let (from, to, capacity) = (line[0].parse().unwrap(), line[1].parse().unwrap(), line[2].parse().unwrap());
-        graph.add_directed_edge(from, to, capacity);
+    let mut times = Vec::new();
+    let mut answers = Vec::new();
+    for algorithm in 0..4 {
+        graph.clear();
+        let result = new_solver(algorithm, num_left_nodes, num_right_nodes, alpha, &mut graph).and_then(|mut solver| {
+            let start = Instant::now();
+            let ans = solver.solve(source, sink)?;
+            Ok((ans, start.elapsed()))
+        });
+        match result {
+            Ok((ans, time)) => {
+                answers.push(ans);
+                times.push(time);
+            }
+            Err(e) => exit_with(&format!("{}: {}", file_path, e)),
+        }
     }
 
-    let mut fi = BipartitePushRelabelFIFO::new(num_left_nodes, num_right_nodes, &mut graph);
-    let start = Instant::now();
-    let fi_ans = fi.solve(source, sink);
-    let fi_time = start.elapsed();
-
-    graph.clear();
-    let mut hi = BipartitePushRelabelHighestLabel::new(num_left_nodes, num_right_nodes, &mut graph);
-    let start = Instant::now();
-    let hi_ans = hi.solve(source, sink);
-    let hi_time = start.elapsed();
+    assert_eq!(answers[0], answers[1]);
+    assert_eq!(answers[0], answers[2]);
+    assert_eq!(answers[0], answers[3]);
+    let name = Path::new(file_path).file_stem().map_or(file_path.as_str(), |stem| stem.to_str().unwrap_or(file_path));
+    println!("{},{},{},{},{}", name, times[0].as_millis(), times[1].as_millis(), times[2].as_millis(), times[3].as_millis());
+}
 
-    graph.clear();
-    let mut dinic = Dinic::new(&mut graph);
-    let start = Instant::now();
-    let dinic_ans = dinic.solve(source, sink);
-    let dinic_time = start.elapsed();
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
 
-    assert_eq!(fi_ans, hi_ans);
-    assert_eq!(fi_ans, dinic_ans);
-    let name = &file_path[7..file_path.len() - 3];
-    println!("{},{},{},{}", name, fi_time.as_millis(), hi_time.as_millis(), dinic_time.as_millis());
+fn new_solver(algorithm: usize, num_left_nodes: usize, num_right_nodes: usize, alpha: usize, graph: &mut Graph) -> Result<Box<dyn MaxFlowSolver + '_>, MaxFlowError> {
+    Ok(match algorithm {
+        0 => {
+            let mut solver = BipartitePushRelabelFIFO::new(num_left_nodes, num_right_nodes, graph)?;
+            solver.set_alpha(alpha);
+            Box::new(solver)
+        }
+        1 => {
+            let mut solver = BipartitePushRelabelHighestLabel::new(num_left_nodes, num_right_nodes, graph)?;
+            solver.set_alpha(alpha);
+            Box::new(solver)
+        }
+        2 => Box::new(Dinic::new(graph)?),
+        _ => Box::new(Pseudoflow::new(graph)?),
+    })
 }
 
+// same instances as data/karz.py
+fn generate(dir: &str, seed: u64) {
+    let mut rng = Random::new(seed);
+    for n in [20000, 30000, 40000] {
+        for ratio in [5, 1000] {
+            for d in [2, 10] {
+                let n1 = n / (ratio + 1);
+                let n2 = n - n1;
+                for name in ["zipf", "hilo", "rope"] {
+                    let instance = match name {
+                        "zipf" => generators::zipf(n1, n2, d, &mut rng),
+                        "hilo" => match generators::hilo(n1, n2, d, &mut rng) {
+                            Some(instance) => instance,
+                            None => continue,
+                        },
+                        _ => match generators::rope(n1, n2, d, &mut rng) {
+                            Some(instance) => instance,
+                            None => continue,
+                        },
+                    };
+                    let path = format!("{}/nodes-{}-ratio-{}-density-{}-{}.in", dir, n, ratio, d, name);
+                    let file = File::create(&path).unwrap_or_else(|e| exit_with(&format!("{}: {}", path, e)));
+                    instance.write(&mut BufWriter::new(file)).unwrap_or_else(|e| exit_with(&format!("{}: {}", path, e)));
+                }
+            }
+        }
+    }
+}
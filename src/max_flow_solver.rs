@@ -0,0 +1,41 @@
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
+use crate::graph::{Edge, Flow, Graph, MinCut};
+
+// common interface of the max flow solvers
+// the flow is stored in the underlying graph, so the accessors read it from there
+pub trait MaxFlowSolver<F: Capacity = Flow> {
+    fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError>;
+
+    // maximum flow from any of sources to any of sinks, without adding a super source or a super sink
+    fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError>;
+
+    fn graph(&self) -> &Graph<F>;
+
+    fn graph_mut(&mut self) -> &mut Graph<F>;
+
+    // the next solve starts from the current flow, see Graph::set_capacity
+    fn set_capacity(&mut self, edge_index: usize, capacity: F) -> Result<(), MaxFlowError> {
+        self.graph_mut().set_capacity(edge_index, capacity)
+    }
+
+    // value of the flow computed by the last solve
+    fn flow_value(&self, sink: usize) -> F {
+        self.graph().excesses.get(sink).copied().unwrap_or(F::zero())
+    }
+
+    // flow on the edge returned by Graph::add_directed_edge
+    fn get_edge(&self, edge_index: usize) -> Edge<F> {
+        self.graph().get_edge(edge_index)
+    }
+
+    // minimum cut corresponding to the flow computed by the last solve
+    fn min_cut(&self, source: usize) -> Result<MinCut, MaxFlowError> {
+        self.graph().min_cut(source)
+    }
+
+    // minimum cut corresponding to the flow computed by the last solve_multi
+    fn min_cut_multi(&self, sources: &[usize]) -> Result<MinCut, MaxFlowError> {
+        self.graph().min_cut_multi(sources)
+    }
+}
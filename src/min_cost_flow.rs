@@ -0,0 +1,224 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use crate::error::MaxFlowError;
+use crate::graph::{Cost, Flow, Graph};
+use crate::max_flow_solver::MaxFlowSolver;
+
+// successive shortest paths with potentials
+// costs are read from Graph::add_directed_edge_with_cost
+pub struct MinCostFlow<'a> {
+    pub graph: &'a mut Graph,
+    potentials: Vec<Cost>,
+    distances: Vec<Cost>,
+    parent: Vec<usize>, // inside edge index of the shortest path tree
+    total_cost: Cost,
+}
+
+impl<'a> MinCostFlow<'a> {
+    pub fn new(graph: &'a mut Graph) -> Result<Self, MaxFlowError> {
+        graph.build()?;
+        let n = graph.num_inside_nodes();
+        Ok(MinCostFlow {
+            graph,
+            potentials: vec![0; n],
+            distances: vec![0; n],
+            parent: vec![usize::MAX; n],
+            total_cost: 0,
+        })
+    }
+
+    // minimum cost maximum flow, return (flow, cost)
+    pub fn solve(&mut self, source: usize, sink: usize) -> Result<(Flow, Cost), MaxFlowError> {
+        self.solve_with_flow_limit(source, sink, Flow::MAX)
+    }
+
+    // O(F * m log n) for a flow value F
+    // minimum cost flow of value min(flow_limit, max flow), return (flow, cost)
+    // the flow of the graph is cleared first, since a flow of the last solve need not have the minimum cost
+    pub fn solve_with_flow_limit(&mut self, source: usize, sink: usize, flow_limit: Flow) -> Result<(Flow, Cost), MaxFlowError> {
+        self.successive_shortest_paths(&[source], &[sink], flow_limit, false)
+    }
+
+    // minimum cost flow of any value, return (flow, cost)
+    // the shortest path costs never decrease, so augmenting stops at the first path of nonnegative cost
+    pub fn solve_min_cost(&mut self, source: usize, sink: usize) -> Result<(Flow, Cost), MaxFlowError> {
+        self.successive_shortest_paths(&[source], &[sink], Flow::MAX, true)
+    }
+
+    // minimum cost maximum flow from any of sources to any of sinks, return (flow, cost)
+    pub fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<(Flow, Cost), MaxFlowError> {
+        self.successive_shortest_paths(sources, sinks, Flow::MAX, false)
+    }
+
+    // the sources hang from a virtual node of potential 0 by edges of cost 0,
+    // so the potential of every source stays at most 0 and a path from the virtual node costs the potential of its sink
+    fn successive_shortest_paths(&mut self, sources: &[usize], sinks: &[usize], flow_limit: Flow, negative_paths_only: bool) -> Result<(Flow, Cost), MaxFlowError> {
+        // build again if edges or nodes were added through graph_mut
+        self.graph.build()?;
+        let n = self.graph.num_inside_nodes();
+        self.potentials.resize(n, 0);
+        self.distances.resize(n, 0);
+        self.parent.resize(n, usize::MAX);
+        self.graph.check_terminals(sources, sinks)?;
+        self.graph.clear();
+        self.total_cost = 0;
+        if self.graph.num_edges() == 0 {
+            return Ok((0, 0));
+        }
+        self.graph.check_capacity_overflow(sources)?;
+        self.init_potentials(sources)?;
+
+        let mut flow = 0 as Flow;
+        while flow < flow_limit {
+            self.dijkstra(sources)?;
+            // reduced costs stay nonnegative with the new potentials
+            for u in 0..self.graph.num_inside_nodes() {
+                if self.distances[u] != Cost::MAX {
+                    self.potentials[u] = self.potentials[u].checked_add(self.distances[u]).ok_or(MaxFlowError::CostOverflow)?;
+                }
+            }
+            let sink = match sinks.iter().filter(|&&t| self.distances[t] != Cost::MAX).min_by_key(|&&t| self.potentials[t]) {
+                Some(&sink) => sink,
+                None => break,
+            };
+            let path_cost = self.potentials[sink];
+            if negative_paths_only && path_cost >= 0 {
+                break;
+            }
+
+            // the path starts at the first node without a parent, which is a source
+            let mut delta = flow_limit - flow;
+            let mut v = sink;
+            while self.parent[v] != usize::MAX {
+                let e = &self.graph.inside_edge_list[self.parent[v]];
+                delta = delta.min(e.residual_capacity());
+                v = self.graph.inside_edge_list[e.rev].to;
+            }
+            let mut v = sink;
+            while self.parent[v] != usize::MAX {
+                let i = self.parent[v];
+                let u = self.graph.inside_edge_list[self.graph.inside_edge_list[i].rev].to;
+                self.graph.push_flow(u, i, delta);
+                v = u;
+            }
+
+            self.total_cost = path_cost
+                .checked_mul(delta)
+                .and_then(|cost| cost.checked_add(self.total_cost))
+                .ok_or(MaxFlowError::CostOverflow)?;
+            flow += delta;
+        }
+
+        if self.graph.validation() {
+            self.graph.validate_flow(sources, sinks)?;
+        }
+        Ok((flow, self.total_cost))
+    }
+
+    // cost of the flow computed by the last solve
+    pub fn total_cost(&self) -> Cost {
+        self.total_cost
+    }
+
+    // O(n * m)
+    // Bellman-Ford from the sources, so that negative costs are allowed
+    // nodes not reachable from the sources are never reached later, so their potentials do not matter
+    fn init_potentials(&mut self, sources: &[usize]) -> Result<(), MaxFlowError> {
+        let n = self.graph.num_inside_nodes();
+        self.potentials.fill(0);
+        if (0..self.graph.num_edges()).all(|edge_index| self.graph.get_edge(edge_index).cost >= 0) {
+            return Ok(());
+        }
+
+        // queue based, a shortest path of n edges contains a negative cycle
+        self.distances.fill(Cost::MAX);
+        let mut in_queue = vec![false; n];
+        let mut path_len = vec![0; n];
+        let mut que = VecDeque::new();
+        for &s in sources {
+            self.distances[s] = 0;
+            in_queue[s] = true;
+            que.push_back(s);
+        }
+        while let Some(u) = que.pop_front() {
+            in_queue[u] = false;
+            for i in self.graph.start[u]..self.graph.start[u + 1] {
+                let e = &self.graph.inside_edge_list[i];
+                if e.residual_capacity() == 0 as Flow {
+                    continue;
+                }
+                let d = self.distances[u].checked_add(self.graph.cost(i)).ok_or(MaxFlowError::CostOverflow)?;
+                if d < self.distances[e.to] {
+                    self.distances[e.to] = d;
+                    path_len[e.to] = path_len[u] + 1;
+                    if path_len[e.to] >= n {
+                        return Err(MaxFlowError::NegativeCostCycle { node: self.graph.original_node(e.to) });
+                    }
+                    if !in_queue[e.to] {
+                        in_queue[e.to] = true;
+                        que.push_back(e.to);
+                    }
+                }
+            }
+        }
+        for u in 0..n {
+            if self.distances[u] != Cost::MAX {
+                self.potentials[u] = self.distances[u];
+            }
+        }
+        Ok(())
+    }
+
+    // O(m log n)
+    // shortest paths from the virtual node with the reduced costs
+    fn dijkstra(&mut self, sources: &[usize]) -> Result<(), MaxFlowError> {
+        self.distances.fill(Cost::MAX);
+        self.parent.fill(usize::MAX);
+        let mut heap = BinaryHeap::new();
+        for &s in sources {
+            debug_assert!(self.potentials[s] <= 0);
+            self.distances[s] = -self.potentials[s];
+            heap.push(Reverse((self.distances[s], s)));
+        }
+        while let Some(Reverse((d, u))) = heap.pop() {
+            if d > self.distances[u] {
+                continue;
+            }
+            for i in self.graph.start[u]..self.graph.start[u + 1] {
+                let e = &self.graph.inside_edge_list[i];
+                if e.residual_capacity() == 0 as Flow {
+                    continue;
+                }
+                let reduced_cost = self.graph.cost(i).checked_add(self.potentials[u]).and_then(|cost| cost.checked_sub(self.potentials[e.to]));
+                let reduced_cost = reduced_cost.ok_or(MaxFlowError::CostOverflow)?;
+                debug_assert!(reduced_cost >= 0);
+                let distance = d.checked_add(reduced_cost).ok_or(MaxFlowError::CostOverflow)?;
+                if distance < self.distances[e.to] {
+                    self.distances[e.to] = distance;
+                    self.parent[e.to] = i;
+                    heap.push(Reverse((distance, e.to)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MaxFlowSolver for MinCostFlow<'_> {
+    fn solve(&mut self, source: usize, sink: usize) -> Result<Flow, MaxFlowError> {
+        MinCostFlow::solve(self, source, sink).map(|(flow, _)| flow)
+    }
+
+    fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<Flow, MaxFlowError> {
+        MinCostFlow::solve_multi(self, sources, sinks).map(|(flow, _)| flow)
+    }
+
+    fn graph(&self) -> &Graph {
+        self.graph
+    }
+
+    fn graph_mut(&mut self) -> &mut Graph {
+        self.graph
+    }
+}
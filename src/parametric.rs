@@ -0,0 +1,224 @@
+use crate::bipartite_push_relabel_highest_label::BipartitePushRelabelHighestLabel;
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
+use crate::graph::{Graph, InsideEdge};
+
+// parametric maximum flow of Gallo, Grigoriadis and Tarjan on the bipartite highest label push-relabel
+// the capacity of a parametric edge is max(0, constant + slope * lambda),
+// nondecreasing for source edges and nonincreasing for sink edges
+// the preflow and the distance labels are reused while lambda grows
+pub struct ParametricMaxFlow<'a> {
+    solver: BipartitePushRelabelHighestLabel<'a, f64>,
+    source: usize,
+    sink: usize,
+    parametric: Vec<Option<(f64, f64)>>, // (constant, slope) by edge index
+    lambda: Option<f64>,                 // parameter of the current preflow
+}
+
+// preflow and labels at some lambda, to warm start from it
+struct Snapshot {
+    lambda: f64,
+    inside_edge_list: Vec<InsideEdge<f64>>,
+    excesses: Vec<f64>,
+    distances: Vec<usize>,
+}
+
+// capacity of a cut as a function of lambda, constant + slope * lambda
+#[derive(Clone, Copy)]
+struct Line {
+    constant: f64,
+    slope: f64,
+}
+
+impl Line {
+    fn at(&self, lambda: f64) -> f64 {
+        self.constant + self.slope * lambda
+    }
+}
+
+impl<'a> ParametricMaxFlow<'a> {
+    pub fn new(num_left_nodes: usize, num_right_nodes: usize, graph: &'a mut Graph<f64>, source: usize, sink: usize) -> Result<Self, MaxFlowError> {
+        // the phases are run directly rather than through solve, so the sides are checked once here
+        let solver = BipartitePushRelabelHighestLabel::new_unchecked(num_left_nodes, num_right_nodes, graph)?;
+        solver.graph.check_terminals(&[source], &[sink])?;
+        solver.validate_bipartition(&[source], &[sink])?;
+        let num_edges = solver.graph.num_edges();
+        Ok(ParametricMaxFlow {
+            solver,
+            source,
+            sink,
+            parametric: vec![None; num_edges],
+            lambda: None,
+        })
+    }
+
+    pub fn graph(&self) -> &Graph<f64> {
+        self.solver.graph
+    }
+
+    // the edge must be directed, and leave source with slope >= 0 or enter sink with slope <= 0
+    pub fn set_parametric_capacity(&mut self, edge_index: usize, constant: f64, slope: f64) -> Result<(), MaxFlowError> {
+        let e = self.solver.graph.get_edge(edge_index);
+        if self.solver.graph.is_undirected(edge_index) || !(e.from == self.source && slope >= 0.0 || e.to == self.sink && slope <= 0.0) {
+            return Err(MaxFlowError::NonMonotoneCapacity { edge_index });
+        }
+        self.parametric[edge_index] = Some((constant, slope));
+        self.lambda = None;
+        Ok(())
+    }
+
+    // return the value of the maximum flow at lambda
+    // a call with a larger lambda than the last one continues from the last preflow, otherwise it starts from scratch
+    pub fn solve(&mut self, lambda: f64) -> Result<f64, MaxFlowError> {
+        let (source, sink) = (self.source, self.sink);
+        match self.lambda {
+            Some(last) if last <= lambda => {
+                self.update_capacities(lambda)?;
+                self.solver.graph.check_capacity_overflow(&[source])?;
+                self.solver.resume_phase1(&[source], &[sink]);
+            }
+            _ => {
+                self.solver.graph.clear();
+                self.update_capacities(lambda)?;
+                self.solver.graph.check_capacity_overflow(&[source])?;
+                self.solver.start_phase1(&[source], &[sink]);
+            }
+        }
+        self.lambda = Some(lambda);
+        Ok(self.solver.graph.excesses[sink])
+    }
+
+    // O(n + m)
+    // source side of the minimum cut of the last solve, the largest one
+    pub fn source_side(&self) -> Vec<usize> {
+        let graph = &self.solver.graph;
+        let mut reaches_sink = vec![false; graph.num_nodes()];
+        let mut stack = vec![self.sink];
+        reaches_sink[self.sink] = true;
+        while let Some(v) = stack.pop() {
+            // e.flow is the residual capacity of e.to -> v
+            for e in graph.neighbors(v) {
+                if Capacity::is_positive(e.flow) && !reaches_sink[e.to] {
+                    reaches_sink[e.to] = true;
+                    stack.push(e.to);
+                }
+            }
+        }
+        (0..graph.num_nodes()).filter(|&u| !reaches_sink[u]).collect()
+    }
+
+    // values of lambda in (low, high) where the minimum cut changes, in increasing order
+    // Eisner and Severance: the cut capacity is concave and piecewise linear in lambda,
+    // so probing where the lines of two minimum cuts cross either finds a breakpoint or a new cut
+    // every probe is warm started from the preflow at the lower end of its interval
+    pub fn breakpoints(&mut self, low: f64, high: f64) -> Result<Vec<f64>, MaxFlowError> {
+        for (edge_index, p) in self.parametric.iter().enumerate() {
+            if let Some((constant, slope)) = *p {
+                if constant + slope * low < 0.0 || constant + slope * high < 0.0 {
+                    return Err(MaxFlowError::NegativeCapacity { edge_index });
+                }
+            }
+        }
+        let mut breakpoints = Vec::new();
+        if low >= high {
+            return Ok(breakpoints);
+        }
+
+        self.solve(low)?;
+        let low_line = self.cut_line(&self.source_side());
+        let low_snapshot = self.snapshot();
+        self.solve(high)?;
+        let high_line = self.cut_line(&self.source_side());
+
+        let mut intervals = vec![(low_snapshot, low_line, high, high_line)];
+        while let Some((snapshot, low_line, interval_high, high_line)) = intervals.pop() {
+            // the same line on the whole interval
+            if low_line.slope.approx_eq(high_line.slope) {
+                continue;
+            }
+            // when an earlier probe hit a breakpoint exactly, the lines cross at an end of the interval
+            let lambda = (high_line.constant - low_line.constant) / (low_line.slope - high_line.slope);
+            if lambda <= snapshot.lambda || lambda >= interval_high {
+                let end = if lambda <= snapshot.lambda { snapshot.lambda } else { interval_high };
+                if low < end && end < high {
+                    breakpoints.push(end);
+                }
+                continue;
+            }
+
+            self.restore(&snapshot);
+            let value = self.solve(lambda)?;
+            let line = self.cut_line(&self.source_side());
+            if low_line.at(lambda).at_most(value) || line.slope.approx_eq(low_line.slope) || line.slope.approx_eq(high_line.slope) {
+                breakpoints.push(lambda);
+                continue;
+            }
+            intervals.push((self.snapshot(), line, interval_high, high_line));
+            intervals.push((snapshot, low_line, lambda, line));
+        }
+
+        breakpoints.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        breakpoints.dedup_by(|a, b| a.approx_eq(*b));
+        Ok(breakpoints)
+    }
+
+    fn capacity(&self, edge_index: usize, lambda: f64) -> Option<f64> {
+        self.parametric[edge_index].map(|(constant, slope)| (constant + slope * lambda).max(0.0))
+    }
+
+    // source edges grow and sink edges shrink, or any change after clear
+    // flow above the new capacity of a sink edge is returned to its tail as excess
+    fn update_capacities(&mut self, lambda: f64) -> Result<(), MaxFlowError> {
+        for edge_index in 0..self.parametric.len() {
+            let capacity = match self.capacity(edge_index, lambda) {
+                Some(capacity) => capacity,
+                None => continue,
+            };
+            let graph = &mut *self.solver.graph;
+            let e = graph.get_edge(edge_index);
+            if e.flow > capacity {
+                let rev = graph.inside_edge_list[graph.inside_edge_index(edge_index)].rev;
+                graph.push_flow(e.to, rev, e.flow - capacity);
+            }
+            graph.set_capacity(edge_index, capacity)?;
+        }
+        Ok(())
+    }
+
+    fn cut_line(&self, source_side: &[usize]) -> Line {
+        let graph = &self.solver.graph;
+        let mut in_source_side = vec![false; graph.num_nodes()];
+        source_side.iter().for_each(|&u| in_source_side[u] = true);
+
+        let mut line = Line { constant: 0.0, slope: 0.0 };
+        for edge_index in 0..graph.num_edges() {
+            let e = graph.get_edge(edge_index);
+            let reversed = graph.is_undirected(edge_index) && in_source_side[e.to] && !in_source_side[e.from];
+            if !(in_source_side[e.from] && !in_source_side[e.to] || reversed) {
+                continue;
+            }
+            let (constant, slope) = self.parametric[edge_index].unwrap_or((e.capacity, 0.0));
+            line.constant += constant;
+            line.slope += slope;
+        }
+        line
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let graph = &self.solver.graph;
+        Snapshot {
+            lambda: self.lambda.unwrap(),
+            inside_edge_list: graph.inside_edge_list.clone(),
+            excesses: graph.excesses.clone(),
+            distances: graph.distances.clone(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) {
+        let graph = &mut *self.solver.graph;
+        graph.inside_edge_list.clone_from(&snapshot.inside_edge_list);
+        graph.excesses.clone_from(&snapshot.excesses);
+        graph.distances.clone_from(&snapshot.distances);
+        self.lambda = Some(snapshot.lambda);
+    }
+}
@@ -0,0 +1,367 @@
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
+use crate::graph::{surplus, Flow, Graph};
+use crate::max_flow_solver::MaxFlowSolver;
+
+const NONE: usize = usize::MAX;
+
+// highest label pseudoflow of Hochbaum (HPF)
+// phase 1 saturates the edges leaving the sources and entering the sinks, which leaves an excess or a deficit at the other nodes,
+// and keeps a forest whose roots hold them: a strong tree (root with an excess) merges into a node of label one less
+// through a residual edge and pushes its excess to the root of that tree, splitting at the edges that saturate
+// the strong nodes that cannot merge anymore are the source side of a minimum cut
+// phase 2 returns the excesses to the sources and the deficits to the sinks, so that the graph holds a maximum flow
+pub struct Pseudoflow<'a, F = Flow> {
+    pub graph: &'a mut Graph<F>,
+
+    excess: Vec<F>, // only roots have an excess or a deficit
+    deficit: Vec<F>,
+    labels: Vec<usize>,
+    label_count: Vec<usize>,
+    strong_roots: Vec<Vec<usize>>, // strong_roots[i] = strong roots of label i
+    highest_label: usize,
+
+    // the forest
+    parent: Vec<usize>, // inside edge index from u to its parent, NONE for the roots
+    first_child: Vec<usize>,
+    next_sibling: Vec<usize>,
+    prev_sibling: Vec<usize>,
+    next_scan: Vec<usize>,    // next child of u to visit in process_root
+    current_edge: Vec<usize>, // next edge of u to look for a merger
+
+    is_terminal: Vec<bool>,
+}
+
+impl<'a, F: Capacity> Pseudoflow<'a, F> {
+    pub fn new(graph: &'a mut Graph<F>) -> Result<Self, MaxFlowError> {
+        graph.build()?;
+        let n = graph.num_inside_nodes();
+        Ok(Pseudoflow {
+            graph,
+
+            excess: vec![F::zero(); n],
+            deficit: vec![F::zero(); n],
+            labels: vec![0; n],
+            label_count: vec![0; n + 1],
+            strong_roots: vec![Vec::new(); n + 1],
+            highest_label: 0,
+
+            parent: vec![NONE; n],
+            first_child: vec![NONE; n],
+            next_sibling: vec![NONE; n],
+            prev_sibling: vec![NONE; n],
+            next_scan: vec![NONE; n],
+            current_edge: vec![0; n],
+
+            is_terminal: vec![false; n],
+        })
+    }
+
+    pub fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        self.solve_multi(&[source], &[sink])
+    }
+
+    // maximum flow from any of sources to any of sinks
+    pub fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        self.rebuild()?;
+        self.graph.check_terminals(sources, sinks)?;
+        if self.graph.num_edges() == 0 {
+            return Ok(F::zero());
+        }
+        // the excesses are bounded by the capacities leaving the sources and the deficits by those entering the sinks
+        self.graph.check_capacity_overflow(sources)?;
+        self.graph.check_capacity_overflow_into(sinks)?;
+        // warm start from the flow of the last solve
+        self.graph.repair_flow(sources, sinks);
+
+        self.find_min_cut(sources, sinks);
+        // phase 2, no flow enters the source side of the minimum cut, so the walks stay on their side and the cut stays saturated
+        self.graph.recover_flow(sources, sinks, &mut self.excess, &mut self.deficit);
+        if self.graph.validation() {
+            self.graph.validate_flow(sources, sinks)?;
+        }
+        Ok(self.graph.flow_value(sinks))
+    }
+
+    // phase 1, the strong nodes and the sources are the source side of a minimum cut,
+    // and the graph holds a pseudoflow whose excesses and deficits are not recovered yet
+    fn find_min_cut(&mut self, sources: &[usize], sinks: &[usize]) {
+        self.init(sources, sinks);
+        while let Some(root) = self.highest_strong_root() {
+            self.process_root(root);
+        }
+    }
+
+    // build again if edges or nodes were added through graph_mut
+    fn rebuild(&mut self) -> Result<(), MaxFlowError> {
+        self.graph.build()?;
+        let n = self.graph.num_inside_nodes();
+        self.excess.resize(n, F::zero());
+        self.deficit.resize(n, F::zero());
+        self.labels.resize(n, 0);
+        self.label_count.resize(n + 1, 0);
+        self.strong_roots.resize(n + 1, Vec::new());
+        for v in [&mut self.parent, &mut self.first_child, &mut self.next_sibling, &mut self.prev_sibling, &mut self.next_scan] {
+            v.resize(n, NONE);
+        }
+        self.current_edge.resize(n, 0);
+        self.is_terminal.resize(n, false);
+        Ok(())
+    }
+
+    // every node is a root of its own, strong nodes of label 1 and weak nodes of label 0
+    fn init(&mut self, sources: &[usize], sinks: &[usize]) {
+        let n = self.graph.num_inside_nodes();
+        self.is_terminal.fill(false);
+        sources.iter().chain(sinks).for_each(|&u| self.is_terminal[u] = true);
+        for &s in sources {
+            for i in self.graph.start[s]..self.graph.start[s + 1] {
+                let residual_capacity = self.graph.inside_edge_list[i].residual_capacity();
+                if !sources.contains(&self.graph.inside_edge_list[i].to) && residual_capacity.is_positive() {
+                    self.graph.augment(i, residual_capacity);
+                }
+            }
+        }
+        for &t in sinks {
+            for i in self.graph.start[t]..self.graph.start[t + 1] {
+                let rev = self.graph.inside_edge_list[i].rev;
+                let residual_capacity = self.graph.inside_edge_list[rev].residual_capacity();
+                if !sinks.contains(&self.graph.inside_edge_list[i].to) && residual_capacity.is_positive() {
+                    self.graph.augment(rev, residual_capacity);
+                }
+            }
+        }
+
+        // inflow and outflow are kept apart, since a deficit cannot be stored in an unsigned capacity type
+        let mut inflow = vec![F::zero(); n];
+        let mut outflow = vec![F::zero(); n];
+        for edge_index in 0..self.graph.num_edges_with_split() {
+            let e = self.graph.get_inside_edge(edge_index);
+            outflow[e.from] += e.flow;
+            inflow[e.to] += e.flow;
+        }
+
+        self.label_count.fill(0);
+        self.strong_roots.iter_mut().for_each(|roots| roots.clear());
+        self.highest_label = 1;
+        for u in 0..n {
+            self.parent[u] = NONE;
+            self.first_child[u] = NONE;
+            self.current_edge[u] = self.graph.start[u];
+            if self.is_terminal[u] {
+                self.excess[u] = F::zero();
+                self.deficit[u] = F::zero();
+                self.labels[u] = n;
+                continue;
+            }
+            self.excess[u] = surplus(inflow[u], outflow[u]);
+            self.deficit[u] = surplus(outflow[u], inflow[u]);
+            self.labels[u] = if self.excess[u].is_positive() { 1 } else { 0 };
+            self.label_count[self.labels[u]] += 1;
+            if self.excess[u].is_positive() {
+                self.strong_roots[1].push(u);
+            }
+        }
+    }
+
+    // a strong root of the highest label that has nodes one label below to merge into
+    // the strong roots of a label with no node below cannot merge anymore, so their trees are lifted to the source side
+    fn highest_strong_root(&mut self) -> Option<usize> {
+        for label in (1..=self.highest_label).rev() {
+            while let Some(root) = self.strong_roots[label].pop() {
+                if self.label_count[label - 1] > 0 {
+                    self.highest_label = label;
+                    return Some(root);
+                }
+                self.lift_all(root);
+            }
+        }
+
+        // weak roots that became strong, a new start from label 1
+        let roots = std::mem::take(&mut self.strong_roots[0]);
+        for &root in roots.iter() {
+            self.label_count[0] -= 1;
+            self.label_count[1] += 1;
+            self.labels[root] = 1;
+        }
+        self.strong_roots[1] = roots;
+        self.highest_label = 1;
+        self.strong_roots[1].pop()
+    }
+
+    // depth first over the nodes of the tree with the label of the root, looking for a merger edge,
+    // a node is relabeled once none of its children has its label, so the labels never decrease from a parent to a child
+    fn process_root(&mut self, root: usize) {
+        let label = self.labels[root];
+        let mut u = root;
+        self.next_scan[root] = self.first_child[root];
+        if let Some(i) = self.find_merger(root, label) {
+            self.merge(i);
+            self.push_excess(root);
+            return;
+        }
+        self.check_children(root);
+
+        loop {
+            while self.next_scan[u] != NONE {
+                let child = self.next_scan[u];
+                self.next_scan[u] = self.next_sibling[child];
+                u = child;
+                self.next_scan[u] = self.first_child[u];
+                if let Some(i) = self.find_merger(u, label) {
+                    self.merge(i);
+                    self.push_excess(root);
+                    return;
+                }
+                self.check_children(u);
+            }
+            if self.parent[u] == NONE {
+                break;
+            }
+            u = self.graph.inside_edge_list[self.parent[u]].to;
+            self.check_children(u);
+        }
+
+        self.strong_roots[self.labels[root]].push(root);
+        self.highest_label = self.highest_label.max(self.labels[root]);
+    }
+
+    // residual edge from u to a node of label - 1, which is in another tree since labels grow from the roots
+    fn find_merger(&mut self, u: usize, label: usize) -> Option<usize> {
+        for i in self.current_edge[u]..self.graph.start[u + 1] {
+            let e = &self.graph.inside_edge_list[i];
+            if self.labels[e.to] + 1 == label && !self.is_terminal[e.to] && e.residual_capacity().is_positive() {
+                self.current_edge[u] = i;
+                return Some(i);
+            }
+        }
+        self.current_edge[u] = self.graph.start[u + 1];
+        None
+    }
+
+    // move next_scan to the next child of the same label, or relabel u if there is none
+    fn check_children(&mut self, u: usize) {
+        while self.next_scan[u] != NONE {
+            if self.labels[self.next_scan[u]] == self.labels[u] {
+                return;
+            }
+            self.next_scan[u] = self.next_sibling[self.next_scan[u]];
+        }
+        self.label_count[self.labels[u]] -= 1;
+        self.labels[u] += 1;
+        self.label_count[self.labels[u]] += 1;
+        self.current_edge[u] = self.graph.start[u];
+    }
+
+    // merger edge i from the strong tree to the weak node: the path from its tail to the strong root is reversed,
+    // so that the tail hangs from the weak node and the strong root below it
+    fn merge(&mut self, i: usize) {
+        let mut child = self.graph.inside_edge_list[self.graph.inside_edge_list[i].rev].to;
+        let mut to_parent = i;
+        loop {
+            let old = self.parent[child];
+            self.cut(child);
+            self.link(child, to_parent);
+            if old == NONE {
+                break;
+            }
+            to_parent = self.graph.inside_edge_list[old].rev;
+            child = self.graph.inside_edge_list[old].to;
+        }
+    }
+
+    // push the excess of u towards its root, a saturated edge splits the tree and its child becomes a strong root
+    fn push_excess(&mut self, mut u: usize) {
+        let mut was_strong = true;
+        while self.excess[u].is_positive() && self.parent[u] != NONE {
+            let a = self.parent[u];
+            let p = self.graph.inside_edge_list[a].to;
+            was_strong = self.excess[p].is_positive();
+            let residual_capacity = self.graph.inside_edge_list[a].residual_capacity();
+            let delta = self.excess[u].min_capacity(residual_capacity);
+            self.graph.augment(a, delta);
+            self.excess[u] -= delta;
+            self.add_excess(p, delta);
+            if self.excess[u].is_positive() {
+                self.cut(u);
+                self.strong_roots[self.labels[u]].push(u);
+            }
+            u = p;
+        }
+        if self.parent[u] == NONE && self.excess[u].is_positive() && !was_strong {
+            self.strong_roots[self.labels[u]].push(u);
+        }
+    }
+
+    fn add_excess(&mut self, u: usize, amount: F) {
+        if amount.at_most(self.deficit[u]) {
+            self.deficit[u] -= amount;
+        } else {
+            self.excess[u] += amount - self.deficit[u];
+            self.deficit[u] = F::zero();
+        }
+    }
+
+    // the tree of root goes to the source side
+    fn lift_all(&mut self, root: usize) {
+        let n = self.graph.num_inside_nodes();
+        let mut stack = vec![root];
+        while let Some(u) = stack.pop() {
+            self.label_count[self.labels[u]] -= 1;
+            self.labels[u] = n;
+            let mut child = self.first_child[u];
+            while child != NONE {
+                stack.push(child);
+                child = self.next_sibling[child];
+            }
+        }
+    }
+
+    // u becomes a root
+    fn cut(&mut self, u: usize) {
+        if self.parent[u] == NONE {
+            return;
+        }
+        let p = self.graph.inside_edge_list[self.parent[u]].to;
+        let (prev, next) = (self.prev_sibling[u], self.next_sibling[u]);
+        if prev == NONE {
+            self.first_child[p] = next;
+        } else {
+            self.next_sibling[prev] = next;
+        }
+        if next != NONE {
+            self.prev_sibling[next] = prev;
+        }
+        self.parent[u] = NONE;
+    }
+
+    // the root u hangs from the head of to_parent
+    fn link(&mut self, u: usize, to_parent: usize) {
+        let p = self.graph.inside_edge_list[to_parent].to;
+        self.parent[u] = to_parent;
+        self.prev_sibling[u] = NONE;
+        self.next_sibling[u] = self.first_child[p];
+        if self.first_child[p] != NONE {
+            self.prev_sibling[self.first_child[p]] = u;
+        }
+        self.first_child[p] = u;
+    }
+}
+
+impl<F: Capacity> MaxFlowSolver<F> for Pseudoflow<'_, F> {
+    fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        Pseudoflow::solve(self, source, sink)
+    }
+
+    fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        Pseudoflow::solve_multi(self, sources, sinks)
+    }
+
+    fn graph(&self) -> &Graph<F> {
+        self.graph
+    }
+
+    fn graph_mut(&mut self) -> &mut Graph<F> {
+        self.graph
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::VecDeque;
+
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
+use crate::graph::{Flow, Graph};
+use crate::max_flow_solver::MaxFlowSolver;
+
+// push relabel on any directed graph, the active nodes are discharged in FIFO order
+pub struct PushRelabelFIFO<'a, F = Flow> {
+    pub graph: &'a mut Graph<F>,
+    alpha: usize,
+    relabel_count: usize,
+
+    active_nodes: VecDeque<usize>,
+    in_que: Vec<bool>,
+
+    current_edge: Vec<usize>,
+    distance_count: Vec<usize>,
+}
+
+impl<'a, F: Capacity> PushRelabelFIFO<'a, F> {
+    pub fn new(graph: &'a mut Graph<F>) -> Result<Self, MaxFlowError> {
+        graph.build()?;
+        let n = graph.num_inside_nodes();
+
+        Ok(PushRelabelFIFO {
+            graph,
+            // without a bound on the length of the paths, the distances drift far from the exact ones between global updates
+            alpha: 6,
+            relabel_count: 0,
+
+            active_nodes: VecDeque::new(),
+            in_que: vec![false; n],
+
+            current_edge: vec![0; n],
+            distance_count: vec![0; n + 1],
+        })
+    }
+
+    // a global update after every alpha * n relabels, 0 for none
+    pub fn set_alpha(&mut self, alpha: usize) {
+        self.alpha = alpha;
+    }
+
+    pub fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        self.solve_multi(&[source], &[sink])
+    }
+
+    // O(n^3)
+    // maximum flow from any of sources to any of sinks
+    pub fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        self.rebuild()?;
+        self.graph.check_terminals(sources, sinks)?;
+        if self.graph.num_edges() == 0 {
+            return Ok(F::zero());
+        }
+        self.graph.check_capacity_overflow(sources)?;
+        // warm start from the flow of the last solve
+        self.graph.repair_flow(sources, sinks);
+        self.pre_process(sources, sinks);
+
+        while let Some(u) = self.active_nodes.pop_front() {
+            debug_assert!(!sources.contains(&u) && !sinks.contains(&u));
+            self.in_que[u] = false;
+            // there is no path from u to sink
+            if self.graph.distances[u] >= self.graph.num_inside_nodes() {
+                continue;
+            }
+            debug_assert!(self.graph.excesses[u].is_positive());
+            self.discharge(u);
+
+            // global update heuristic
+            if self.alpha != 0 && self.relabel_count > self.alpha * self.graph.num_inside_nodes() {
+                self.global_update(sources, sinks);
+            }
+        }
+
+        self.graph.push_excess_back_to_sources(sources, sinks);
+        if self.graph.validation() {
+            self.graph.validate_flow(sources, sinks)?;
+        }
+        Ok(self.graph.flow_value(sinks))
+    }
+
+    // build again if edges or nodes were added through graph_mut, see Graph::build
+    fn rebuild(&mut self) -> Result<(), MaxFlowError> {
+        self.graph.build()?;
+        let n = self.graph.num_inside_nodes();
+        self.in_que.resize(n, false);
+        self.current_edge.resize(n, 0);
+        self.distance_count.resize(n + 1, 0);
+        Ok(())
+    }
+
+    fn pre_process(&mut self, sources: &[usize], sinks: &[usize]) {
+        // push from source
+        for &s in sources {
+            for i in self.graph.start[s]..self.graph.start[s + 1] {
+                let delta = self.graph.inside_edge_list[i].residual_capacity();
+                self.graph.push_flow(s, i, delta);
+            }
+        }
+        self.global_update(sources, sinks);
+    }
+
+    // exact distances to sink, then every node with an excess is active again
+    fn global_update(&mut self, sources: &[usize], sinks: &[usize]) {
+        let n = self.graph.num_inside_nodes();
+        self.relabel_count = 0;
+        self.graph.global_relabel(sources, sinks, n, &mut self.distance_count);
+        self.current_edge.copy_from_slice(&self.graph.start[..n]);
+
+        self.active_nodes.clear();
+        self.in_que.fill(false);
+        // the terminals are never active
+        for &u in sources.iter().chain(sinks) {
+            self.in_que[u] = true;
+        }
+        for u in 0..n {
+            self.enqueue(u);
+        }
+    }
+
+    fn enqueue(&mut self, u: usize) {
+        if self.in_que[u] || !self.graph.excesses[u].is_positive() || self.graph.distances[u] >= self.graph.num_inside_nodes() {
+            return;
+        }
+
+        self.in_que[u] = true;
+        self.active_nodes.push_back(u);
+    }
+
+    // push along the admissible edges from the current edge, and relabel u once there is none left
+    fn discharge(&mut self, u: usize) {
+        for i in self.current_edge[u]..self.graph.start[u + 1] {
+            self.current_edge[u] = i;
+            if !self.graph.is_admissible_edge(u, i) {
+                continue;
+            }
+
+            let delta = self.graph.excesses[u].min_capacity(self.graph.inside_edge_list[i].residual_capacity());
+            self.graph.push_flow(u, i, delta);
+            // enqueue ignores nodes that are already active and the terminals
+            self.enqueue(self.graph.inside_edge_list[i].to);
+
+            if self.graph.excesses[u].is_zero() {
+                return;
+            }
+        }
+
+        // relabel
+        if self.graph.relabel(u, &mut self.distance_count) {
+            self.relabel_count += 1;
+        }
+        self.current_edge[u] = self.graph.start[u];
+        self.enqueue(u);
+    }
+}
+
+impl<F: Capacity> MaxFlowSolver<F> for PushRelabelFIFO<'_, F> {
+    fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        PushRelabelFIFO::solve(self, source, sink)
+    }
+
+    fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        PushRelabelFIFO::solve_multi(self, sources, sinks)
+    }
+
+    fn graph(&self) -> &Graph<F> {
+        self.graph
+    }
+
+    fn graph_mut(&mut self) -> &mut Graph<F> {
+        self.graph
+    }
+}
@@ -0,0 +1,189 @@
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
+use crate::graph::{Flow, Graph};
+use crate::max_flow_solver::MaxFlowSolver;
+
+// push relabel on any directed graph, the active node of the highest distance is discharged first
+pub struct PushRelabelHighestLabel<'a, F = Flow> {
+    pub graph: &'a mut Graph<F>,
+    alpha: usize,
+    relabel_count: usize,
+
+    buckets: Vec<Vec<usize>>, // buckets[i] = active nodes with distance i
+    in_bucket: Vec<bool>,
+    bucket_idx: usize,
+
+    current_edge: Vec<usize>,
+    distance_count: Vec<usize>,
+}
+
+impl<'a, F: Capacity> PushRelabelHighestLabel<'a, F> {
+    pub fn new(graph: &'a mut Graph<F>) -> Result<Self, MaxFlowError> {
+        graph.build()?;
+        let n = graph.num_inside_nodes();
+
+        Ok(PushRelabelHighestLabel {
+            graph,
+            // without a bound on the length of the paths, the distances drift far from the exact ones between global updates
+            alpha: 6,
+            relabel_count: 0,
+
+            buckets: vec![Vec::new(); n],
+            in_bucket: vec![false; n],
+            bucket_idx: 0,
+
+            current_edge: vec![0; n],
+            distance_count: vec![0; n + 1],
+        })
+    }
+
+    // a global update after every alpha * n relabels, 0 for none
+    pub fn set_alpha(&mut self, alpha: usize) {
+        self.alpha = alpha;
+    }
+
+    pub fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        self.solve_multi(&[source], &[sink])
+    }
+
+    // O(n^2 * sqrt(m))
+    // maximum flow from any of sources to any of sinks
+    pub fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        self.rebuild()?;
+        self.graph.check_terminals(sources, sinks)?;
+        if self.graph.num_edges() == 0 {
+            return Ok(F::zero());
+        }
+        self.graph.check_capacity_overflow(sources)?;
+        // warm start from the flow of the last solve
+        self.graph.repair_flow(sources, sinks);
+        self.pre_process(sources, sinks);
+        self.discharge_active_nodes(sources, sinks);
+
+        self.graph.push_excess_back_to_sources(sources, sinks);
+        if self.graph.validation() {
+            self.graph.validate_flow(sources, sinks)?;
+        }
+        Ok(self.graph.flow_value(sinks))
+    }
+
+    // build again if edges or nodes were added through graph_mut, see Graph::build
+    fn rebuild(&mut self) -> Result<(), MaxFlowError> {
+        self.graph.build()?;
+        let n = self.graph.num_inside_nodes();
+        self.buckets.resize(n, Vec::new());
+        self.in_bucket.resize(n, false);
+        self.current_edge.resize(n, 0);
+        self.distance_count.resize(n + 1, 0);
+        Ok(())
+    }
+
+    fn pre_process(&mut self, sources: &[usize], sinks: &[usize]) {
+        // push from source
+        for &s in sources {
+            for i in self.graph.start[s]..self.graph.start[s + 1] {
+                let delta = self.graph.inside_edge_list[i].residual_capacity();
+                self.graph.push_flow(s, i, delta);
+            }
+        }
+        self.global_update(sources, sinks);
+    }
+
+    // exact distances to sink, then every node with an excess is active again
+    fn global_update(&mut self, sources: &[usize], sinks: &[usize]) {
+        let n = self.graph.num_inside_nodes();
+        self.relabel_count = 0;
+        self.graph.global_relabel(sources, sinks, n, &mut self.distance_count);
+        self.current_edge.copy_from_slice(&self.graph.start[..n]);
+
+        self.buckets.iter_mut().for_each(|bucket| bucket.clear());
+        self.in_bucket.fill(false);
+        self.bucket_idx = 0;
+        // the terminals are never active
+        for &u in sources.iter().chain(sinks) {
+            self.in_bucket[u] = true;
+        }
+        for u in 0..n {
+            self.enqueue(u);
+        }
+    }
+
+    fn discharge_active_nodes(&mut self, sources: &[usize], sinks: &[usize]) {
+        loop {
+            if self.buckets[self.bucket_idx].is_empty() {
+                if self.bucket_idx == 0 {
+                    break;
+                }
+                self.bucket_idx -= 1;
+                continue;
+            }
+
+            let u = self.buckets[self.bucket_idx].pop().unwrap();
+            self.in_bucket[u] = false;
+            // a gap relabeling may have lifted u after it was enqueued
+            if self.graph.distances[u] != self.bucket_idx {
+                continue;
+            }
+            self.discharge(u);
+
+            // global update heuristic
+            if self.alpha != 0 && self.relabel_count > self.alpha * self.graph.num_inside_nodes() {
+                self.global_update(sources, sinks);
+            }
+        }
+    }
+
+    fn enqueue(&mut self, u: usize) {
+        if self.in_bucket[u] || !self.graph.excesses[u].is_positive() || self.graph.distances[u] >= self.graph.num_inside_nodes() {
+            return;
+        }
+
+        self.in_bucket[u] = true;
+        self.buckets[self.graph.distances[u]].push(u);
+        self.bucket_idx = self.bucket_idx.max(self.graph.distances[u]);
+    }
+
+    // push along the admissible edges from the current edge, and relabel u once there is none left
+    fn discharge(&mut self, u: usize) {
+        for i in self.current_edge[u]..self.graph.start[u + 1] {
+            self.current_edge[u] = i;
+            if !self.graph.is_admissible_edge(u, i) {
+                continue;
+            }
+
+            let delta = self.graph.excesses[u].min_capacity(self.graph.inside_edge_list[i].residual_capacity());
+            self.graph.push_flow(u, i, delta);
+            // enqueue ignores nodes that are already active and the terminals
+            self.enqueue(self.graph.inside_edge_list[i].to);
+
+            if self.graph.excesses[u].is_zero() {
+                return;
+            }
+        }
+
+        // relabel
+        if self.graph.relabel(u, &mut self.distance_count) {
+            self.relabel_count += 1;
+        }
+        self.current_edge[u] = self.graph.start[u];
+        self.enqueue(u);
+    }
+}
+
+impl<F: Capacity> MaxFlowSolver<F> for PushRelabelHighestLabel<'_, F> {
+    fn solve(&mut self, source: usize, sink: usize) -> Result<F, MaxFlowError> {
+        PushRelabelHighestLabel::solve(self, source, sink)
+    }
+
+    fn solve_multi(&mut self, sources: &[usize], sinks: &[usize]) -> Result<F, MaxFlowError> {
+        PushRelabelHighestLabel::solve_multi(self, sources, sinks)
+    }
+
+    fn graph(&self) -> &Graph<F> {
+        self.graph
+    }
+
+    fn graph_mut(&mut self) -> &mut Graph<F> {
+        self.graph
+    }
+}
@@ -0,0 +1,88 @@
+use crate::capacity::Capacity;
+use crate::error::MaxFlowError;
+use crate::graph::{Graph, MinCut};
+
+// O(n * (n^2 + m))
+// global minimum cut of Stoer and Wagner, return (value, cut) where cut.source_side is one side of the cut
+// every phase adds the nodes in maximum adjacency order, the last node alone is a minimum cut between the last two,
+// and the last two are merged for the next phase
+// the capacities must be symmetric, see Graph::check_symmetric: an undirected edge counts once,
+// and a pair of directed edges counts as one undirected edge
+// with less than two nodes there is no cut, and the value is F::max_value()
+pub fn stoer_wagner<F: Capacity>(graph: &Graph<F>) -> Result<(F, MinCut), MaxFlowError> {
+    graph.check_symmetric()?;
+    let n = graph.num_nodes();
+    let mut adjacency: Vec<Vec<(usize, F)>> = vec![Vec::new(); n];
+    for edge_index in 0..graph.num_edges() {
+        let e = graph.get_edge(edge_index);
+        if e.from == e.to || !graph.is_undirected(edge_index) && e.from > e.to {
+            continue;
+        }
+        adjacency[e.from].push((e.to, e.capacity));
+        adjacency[e.to].push((e.from, e.capacity));
+    }
+
+    // merged nodes keep a representative, whose adjacency takes theirs
+    let mut representative: Vec<usize> = (0..n).collect();
+    let mut members: Vec<Vec<usize>> = (0..n).map(|u| vec![u]).collect();
+    let mut active: Vec<usize> = (0..n).collect();
+    let mut best = (F::max_value(), Vec::new());
+
+    let mut weights = vec![F::zero(); n];
+    let mut added = vec![false; n];
+    while active.len() > 1 {
+        active.iter().for_each(|&u| {
+            weights[u] = F::zero();
+            added[u] = false;
+        });
+        let (mut prev, mut last) = (usize::MAX, usize::MAX);
+        for _ in 0..active.len() {
+            let u = active.iter().copied().filter(|&u| !added[u]).reduce(|best, u| if weights[best] < weights[u] { u } else { best }).unwrap();
+            added[u] = true;
+            (prev, last) = (last, u);
+            for &(v, capacity) in adjacency[u].iter() {
+                let v = find(&mut representative, v);
+                if !added[v] {
+                    weights[v] += capacity;
+                }
+            }
+        }
+
+        if weights[last] < best.0 {
+            best = (weights[last], members[last].clone());
+        }
+        let last_members = std::mem::take(&mut members[last]);
+        members[prev].extend(last_members);
+        let last_adjacency = std::mem::take(&mut adjacency[last]);
+        adjacency[prev].extend(last_adjacency);
+        representative[last] = prev;
+        active.retain(|&u| u != last);
+    }
+
+    let (value, mut source_side) = best;
+    source_side.sort_unstable();
+    let mut in_source_side = vec![false; n];
+    source_side.iter().for_each(|&u| in_source_side[u] = true);
+    let edges = (0..graph.num_edges())
+        .filter(|&edge_index| !graph.is_removed(edge_index))
+        .filter(|&edge_index| {
+            let e = graph.get_edge(edge_index);
+            in_source_side[e.from] && !in_source_side[e.to] || graph.is_undirected(edge_index) && in_source_side[e.to] && !in_source_side[e.from]
+        })
+        .collect();
+    let cut = MinCut {
+        source_side,
+        edges,
+        nodes: Vec::new(),
+    };
+    Ok((value, cut))
+}
+
+// representative of a merged node, with path halving
+fn find(representative: &mut [usize], mut u: usize) -> usize {
+    while representative[u] != u {
+        representative[u] = representative[representative[u]];
+        u = representative[u];
+    }
+    u
+}
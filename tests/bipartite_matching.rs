@@ -0,0 +1,75 @@
+use maxflow::generators::Random;
+use maxflow::{BipartiteMatching, MaxFlowError};
+
+// the pairs form a matching and use only the given edges
+fn check_matching(pairs: &[(usize, usize)], edges: &[(usize, usize, i64)]) {
+    for (i, &(u, v)) in pairs.iter().enumerate() {
+        assert!(edges.iter().any(|&(left, right, _)| (left, right) == (u, v)));
+        assert!(pairs[i + 1..].iter().all(|&(x, y)| x != u && y != v), "{:?}", pairs);
+    }
+}
+
+// (largest size, largest weight) over every subset of edges that is a matching
+fn brute_force(edges: &[(usize, usize, i64)]) -> (usize, i64) {
+    let (mut size, mut weight) = (0, 0);
+    for subset in 0..1u32 << edges.len() {
+        let chosen: Vec<_> = (0..edges.len()).filter(|&i| subset >> i & 1 == 1).map(|i| edges[i]).collect();
+        let is_matching = chosen.iter().enumerate().all(|(i, &(u, v, _))| chosen[i + 1..].iter().all(|&(x, y, _)| x != u && y != v));
+        if is_matching {
+            size = size.max(chosen.len());
+            weight = weight.max(chosen.iter().map(|&(_, _, w)| w).sum());
+        }
+    }
+    (size, weight)
+}
+
+#[test]
+fn known_instances() {
+    // the greedy choice of (0, 0) would block the perfect matching
+    let matching = BipartiteMatching::new(3, 3, &[(0, 0), (0, 1), (1, 0), (2, 1), (2, 2)]).unwrap();
+    let mut pairs = matching.maximum_matching().unwrap();
+    pairs.sort_unstable();
+    assert_eq!(pairs.len(), 3);
+    check_matching(&pairs, &[(0, 0, 1), (0, 1, 1), (1, 0, 1), (2, 1, 1), (2, 2, 1)]);
+
+    // more left nodes than right nodes
+    let pairs = BipartiteMatching::new(3, 1, &[(0, 0), (1, 0), (2, 0)]).unwrap().maximum_matching().unwrap();
+    assert_eq!(pairs.len(), 1);
+
+    // the heaviest matching takes one heavy edge over two light ones, and leaves out a negative weight
+    let edges = [(0, 0, 5), (0, 1, 2), (1, 0, 2), (1, 1, -1)];
+    let (pairs, weight) = BipartiteMatching::with_weights(2, 2, &edges).unwrap().maximum_weight_matching().unwrap();
+    assert_eq!((pairs, weight), (vec![(0, 0)], 5));
+    let edges = [(0, 0, 3), (0, 1, 2), (1, 0, 2), (1, 1, 0)];
+    let (mut pairs, weight) = BipartiteMatching::with_weights(2, 2, &edges).unwrap().maximum_weight_matching().unwrap();
+    pairs.sort_unstable();
+    assert_eq!((pairs, weight), (vec![(0, 1), (1, 0)], 4));
+
+    assert!(matches!(BipartiteMatching::new(2, 2, &[(2, 0)]), Err(MaxFlowError::NodeOutOfRange { node: 2, .. })));
+}
+
+#[test]
+fn matches_brute_force() {
+    let mut rng = Random::new(59);
+    for _ in 0..200 {
+        let (n1, n2) = (rng.gen_range(1, 4) as usize, rng.gen_range(1, 4) as usize);
+        let mut edges = vec![];
+        for _ in 0..rng.gen_range(0, 9) {
+            let (u, v) = (rng.gen_range(0, n1 as u64 - 1) as usize, rng.gen_range(0, n2 as u64 - 1) as usize);
+            if !edges.iter().any(|&(x, y, _)| (x, y) == (u, v)) {
+                edges.push((u, v, rng.gen_range(0, 12) as i64 - 3));
+            }
+        }
+        let (size, weight) = brute_force(&edges);
+
+        let unweighted: Vec<_> = edges.iter().map(|&(u, v, _)| (u, v)).collect();
+        let pairs = BipartiteMatching::new(n1, n2, &unweighted).unwrap().maximum_matching().unwrap();
+        check_matching(&pairs, &edges);
+        assert_eq!(pairs.len(), size, "{:?}", edges);
+
+        let (pairs, total) = BipartiteMatching::with_weights(n1, n2, &edges).unwrap().maximum_weight_matching().unwrap();
+        check_matching(&pairs, &edges);
+        assert_eq!(total, weight, "{:?}", edges);
+        assert_eq!(pairs.iter().map(|&(u, v)| edges.iter().find(|e| (e.0, e.1) == (u, v)).unwrap().2).sum::<i64>(), weight);
+    }
+}
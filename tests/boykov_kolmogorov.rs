@@ -0,0 +1,69 @@
+mod common;
+
+use common::{dinic, min_cut_capacity, random_graph};
+use maxflow::generators::{self, Random};
+use maxflow::{BoykovKolmogorov, Graph, MaxFlowSolver};
+
+#[test]
+fn matches_dinic_on_random_graphs() {
+    let mut rng = Random::new(5);
+    for _ in 0..200 {
+        let n = rng.gen_range(2, 30) as usize;
+        let mut graph = random_graph(&mut rng, n, 120, false);
+        let want = dinic(&graph, &[0], &[n - 1]);
+        graph.set_validation(true);
+        let mut solver = BoykovKolmogorov::new(&mut graph).unwrap();
+        assert_eq!(solver.solve(0, n - 1).unwrap(), want);
+        assert_eq!(min_cut_capacity(solver.graph(), &[0]), want);
+    }
+}
+
+#[test]
+fn matches_dinic_on_bipartite_instances() {
+    let mut rng = Random::new(7);
+    for instance in [generators::hilo(30, 60, 5, &mut rng).unwrap(), generators::zipf(30, 60, 5, &mut rng), generators::rope(30, 60, 5, &mut rng).unwrap()] {
+        let (source, sink) = (instance.source(), instance.sink());
+        let mut graph = instance.to_graph();
+        let want = dinic(&graph, &[source], &[sink]);
+        graph.set_validation(true);
+        assert_eq!(BoykovKolmogorov::new(&mut graph).unwrap().solve(source, sink).unwrap(), want);
+    }
+}
+
+#[test]
+fn grid_with_tweights_matches_dinic() {
+    let (width, height) = (12, 9);
+    let (source, sink) = (width * height, width * height + 1);
+    let mut graph = Graph::with_num_nodes(width * height + 2);
+    let mut rng = Random::new(3);
+    for y in 0..height {
+        for x in 0..width {
+            let u = y * width + x;
+            if x + 1 < width {
+                graph.add_undirected_edge(u, u + 1, rng.gen_range(1, 10) as i64);
+            }
+            if y + 1 < height {
+                graph.add_undirected_edge(u, u + width, rng.gen_range(1, 10) as i64);
+            }
+        }
+    }
+    graph.set_validation(true);
+    let mut solver = BoykovKolmogorov::with_terminals(&mut graph, source, sink).unwrap();
+
+    // the second round changes the terminal weights of a solved graph, which starts from the previous flow
+    for _ in 0..2 {
+        for u in 0..width * height {
+            let (to_source, to_sink) = (rng.gen_range(0, 20) as i64, rng.gen_range(0, 20) as i64);
+            solver.set_tweights(u, to_source, to_sink).unwrap();
+        }
+        let value = solver.solve(source, sink).unwrap();
+        assert_eq!(value, dinic(solver.graph(), &[source], &[sink]));
+    }
+}
+
+#[test]
+fn tweights_need_terminals() {
+    let mut graph = Graph::with_num_nodes(3);
+    graph.add_directed_edge(0, 1, 1);
+    assert!(BoykovKolmogorov::new(&mut graph).unwrap().set_tweights(1, 2, 3).is_err());
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::{dinic, random_graph};
+use maxflow::generators::Random;
+use maxflow::*;
+
+// every solver for general graphs on its own graph with edges, the value and the source side of the minimum cut
+fn solve_all<F: Capacity>(n: usize, edges: &[(usize, usize, F)], source: usize, sink: usize) -> Vec<(F, Vec<usize>)> {
+    let mut results = vec![];
+    for algorithm in 0..5 {
+        let mut graph = Graph::<F>::with_num_nodes(n);
+        for &(u, v, capacity) in edges {
+            graph.add_directed_edge(u, v, capacity);
+        }
+        graph.set_validation(true);
+        let value = match algorithm {
+            0 => Dinic::new(&mut graph).unwrap().solve(source, sink),
+            1 => PushRelabelFIFO::new(&mut graph).unwrap().solve(source, sink),
+            2 => PushRelabelHighestLabel::new(&mut graph).unwrap().solve(source, sink),
+            3 => Pseudoflow::new(&mut graph).unwrap().solve(source, sink),
+            _ => BoykovKolmogorov::new(&mut graph).unwrap().solve(source, sink),
+        };
+        results.push((value.unwrap(), graph.min_cut(source).unwrap().source_side));
+    }
+    results
+}
+
+#[test]
+fn edges_into_the_source_do_not_overflow() {
+    let mut graph = Graph::<i64>::default();
+    graph.add_directed_edge(0, 1, i64::MAX / 2 + 1);
+    graph.add_directed_edge(1, 0, i64::MAX / 2 + 1);
+    graph.add_directed_edge(1, 2, 1);
+    assert_eq!(Dinic::new(&mut graph).unwrap().solve(0, 2).unwrap(), 1);
+
+    // an undirected edge counts once
+    let mut graph = Graph::<i64>::default();
+    graph.add_undirected_edge(0, 1, i64::MAX / 4 + 1).unwrap();
+    graph.add_directed_edge(0, 2, i64::MAX / 2);
+    graph.add_directed_edge(1, 2, 1);
+    assert_eq!(PushRelabelFIFO::new(&mut graph).unwrap().solve(0, 2).unwrap(), i64::MAX / 2 + 1);
+
+    // the same holds for the edges leaving the sinks of a pseudoflow
+    let mut graph = Graph::<i64>::default();
+    graph.add_directed_edge(0, 1, 1);
+    graph.add_directed_edge(1, 2, i64::MAX / 2 + 1);
+    graph.add_directed_edge(2, 1, i64::MAX / 2 + 1);
+    assert_eq!(Pseudoflow::new(&mut graph).unwrap().solve(0, 2).unwrap(), 1);
+}
+
+#[test]
+fn capacity_overflow_is_reported() {
+    let mut graph = Graph::<u32>::default();
+    graph.add_directed_edge(0, 1, u32::MAX);
+    graph.add_directed_edge(0, 2, 1);
+    graph.add_directed_edge(1, 3, 1);
+    graph.add_directed_edge(2, 3, 1);
+    assert_eq!(Dinic::new(&mut graph).unwrap().solve(0, 3), Err(MaxFlowError::CapacityOverflow { node: 0 }));
+
+    let mut graph = Graph::<i32>::default();
+    graph.add_directed_edge(0, 1, i32::MAX);
+    graph.add_directed_edge(2, 1, 1);
+    graph.add_directed_edge(1, 3, 1);
+    assert_eq!(Dinic::new(&mut graph).unwrap().solve_multi(&[0], &[3]).unwrap(), 1);
+    assert_eq!(Dinic::new(&mut graph).unwrap().solve_multi(&[0, 2], &[3]), Err(MaxFlowError::CapacityOverflow { node: 2 }));
+
+    let mut graph = Graph::<i32>::default();
+    graph.add_directed_edge(0, 1, 1);
+    graph.add_directed_edge(1, 2, i32::MAX);
+    graph.add_directed_edge(0, 2, 1);
+    assert_eq!(Pseudoflow::new(&mut graph).unwrap().solve(0, 2), Err(MaxFlowError::CapacityOverflow { node: 2 }));
+}
+
+#[test]
+fn min_of_integers_is_not_ambiguous() {
+    // Capacity is in scope through the glob import, Ord::min must still resolve
+    let (a, b): (i64, u32) = (3, 4);
+    assert_eq!(a.min(2), 2);
+    assert_eq!(b.min(5), 4);
+    assert_eq!(a.min_capacity(2), 2);
+}
+
+#[test]
+fn u32_and_f64_match_i64() {
+    let mut rng = Random::new(29);
+    for _ in 0..100 {
+        let n = rng.gen_range(4, 20) as usize;
+        let graph = random_graph(&mut rng, n, 80, false);
+        let want = dinic(&graph, &[0], &[n - 1]);
+        let edges: Vec<_> = (0..graph.num_edges()).map(|edge_index| graph.get_edge(edge_index)).collect();
+        let edges_u32: Vec<_> = edges.iter().map(|e| (e.from, e.to, e.capacity as u32)).collect();
+        for (value, _) in solve_all(n, &edges_u32, 0, n - 1) {
+            assert_eq!(value as i64, want);
+        }
+        // tenths are not exact in f64, so the flows carry rounding errors
+        let edges_f64: Vec<_> = edges.iter().map(|e| (e.from, e.to, e.capacity as f64 / 10.0)).collect();
+        for (value, _) in solve_all(n, &edges_f64, 0, n - 1) {
+            assert!(value.approx_eq(want as f64 / 10.0), "{} {}", value, want);
+        }
+    }
+}
+
+#[test]
+fn f64_rounding_within_epsilon() {
+    // 0.1 + 0.2 is a bit more than 0.3, so the path 0 -> 1 -> 3 leaves a residual of about 5e-17 on 0 -> 1,
+    // which must count as saturated rather than as an edge to push along
+    let edges = [(0, 1, 0.1 + 0.2), (1, 3, 0.3), (0, 2, 0.7), (2, 3, 0.1 * 7.0)];
+    for (algorithm, (value, source_side)) in solve_all(4, &edges, 0, 3).into_iter().enumerate() {
+        assert!(value.approx_eq(1.0), "algorithm {} {}", algorithm, value);
+        assert_eq!(source_side, vec![0], "algorithm {}", algorithm);
+    }
+}
@@ -0,0 +1,41 @@
+use maxflow::generators::Random;
+use maxflow::Graph;
+
+// n nodes, undirected edges and pairs of directed edges of the same capacity, so that the cut capacities are symmetric
+pub fn random_symmetric_graph(rng: &mut Random, n: usize) -> Graph {
+    let mut graph = Graph::with_num_nodes(n);
+    for _ in 0..rng.gen_range(0, 2 * n as u64) {
+        let (u, v) = (rng.gen_range(0, n as u64 - 1) as usize, rng.gen_range(0, n as u64 - 1) as usize);
+        let capacity = rng.gen_range(1, 9) as i64;
+        if u == v {
+            continue;
+        }
+        if rng.gen_range(0, 1) == 0 {
+            graph.add_undirected_edge(u, v, capacity);
+        } else {
+            graph.add_directed_edge(u, v, capacity);
+            graph.add_directed_edge(v, u, capacity);
+        }
+    }
+    graph
+}
+
+// capacity of the edges leaving the nodes with in_side set, an undirected edge in either direction
+pub fn cut_capacity(graph: &Graph, in_side: &[bool]) -> i64 {
+    (0..graph.num_edges())
+        .map(|edge_index| (graph.get_edge(edge_index), graph.is_undirected(edge_index)))
+        .filter(|(e, undirected)| in_side[e.from] && !in_side[e.to] || *undirected && in_side[e.to] && !in_side[e.from])
+        .map(|(e, _)| e.capacity)
+        .sum()
+}
+
+// every side given by a bit mask over the nodes, with its capacity
+pub fn all_cuts(graph: &Graph) -> Vec<(u32, i64)> {
+    let n = graph.num_nodes();
+    (0..1u32 << n)
+        .map(|mask| {
+            let in_side: Vec<bool> = (0..n).map(|u| mask >> u & 1 == 1).collect();
+            (mask, cut_capacity(graph, &in_side))
+        })
+        .collect()
+}
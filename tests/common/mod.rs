@@ -0,0 +1,78 @@
+#![allow(dead_code)]
+pub mod cuts;
+
+use maxflow::generators::Random;
+use maxflow::{Dinic, Graph};
+
+// the same edges and node capacities without the flow, so that a solve starts cold
+pub fn copy(graph: &Graph) -> Graph {
+    copy_without_node_capacities(graph, &[])
+}
+
+// copy, leaving out the capacities of the nodes in skipped
+fn copy_without_node_capacities(graph: &Graph, skipped: &[usize]) -> Graph {
+    let mut copy = Graph::with_num_nodes(graph.num_nodes());
+    for edge_index in 0..graph.num_edges() {
+        let e = graph.get_edge(edge_index);
+        match graph.is_undirected(edge_index) {
+            true => copy.add_undirected_edge(e.from, e.to, e.capacity),
+            false => copy.add_directed_edge(e.from, e.to, e.capacity),
+        };
+    }
+    for u in (0..graph.num_nodes()).filter(|u| !skipped.contains(u)) {
+        if let Some(capacity) = graph.node_capacity(u) {
+            copy.set_node_capacity(u, capacity).unwrap();
+        }
+    }
+    copy
+}
+
+// total capacity of the edges that can carry flow out of u, or into u if leaving is not set
+fn incident_capacity(graph: &Graph, u: usize, leaving: bool) -> i64 {
+    (0..graph.num_edges())
+        .map(|edge_index| (graph.get_edge(edge_index), graph.is_undirected(edge_index)))
+        .filter(|(e, undirected)| (if leaving { e.from } else { e.to }) == u || *undirected && (e.from == u || e.to == u))
+        .map(|(e, _)| e.capacity)
+        .sum()
+}
+
+// the value of a maximum flow by Dinic on a copy, graph is left as it is
+// several terminals are joined to a super source and a super sink by edges that do not limit the flow,
+// so that the value does not depend on solve_multi
+// a sink becomes an inner node there, and its capacity is left out, since it does not bound the flow into a sink
+pub fn dinic(graph: &Graph, sources: &[usize], sinks: &[usize]) -> i64 {
+    if let ([source], [sink]) = (sources, sinks) {
+        return Dinic::new(&mut copy(graph)).unwrap().solve(*source, *sink).unwrap();
+    }
+    let mut copy = copy_without_node_capacities(graph, sinks);
+    let (source, sink) = (copy.add_node(), copy.add_node());
+    for &u in sources {
+        copy.add_directed_edge(source, u, incident_capacity(graph, u, true));
+    }
+    for &u in sinks {
+        copy.add_directed_edge(u, sink, incident_capacity(graph, u, false));
+    }
+    Dinic::new(&mut copy).unwrap().solve(source, sink).unwrap()
+}
+
+// n nodes and up to max_edges edges of capacity 1 to 100, a quarter of them undirected if undirected is set
+pub fn random_graph(rng: &mut Random, n: usize, max_edges: u64, undirected: bool) -> Graph {
+    let mut graph = Graph::with_num_nodes(n);
+    for _ in 0..rng.gen_range(0, max_edges) {
+        let (u, v) = (rng.gen_range(0, n as u64 - 1) as usize, rng.gen_range(0, n as u64 - 1) as usize);
+        if u == v {
+            continue;
+        }
+        if undirected && rng.gen_range(0, 4) == 0 {
+            graph.add_undirected_edge(u, v, rng.gen_range(1, 100) as i64);
+        } else {
+            graph.add_directed_edge(u, v, rng.gen_range(1, 100) as i64);
+        }
+    }
+    graph
+}
+
+// total capacity of the edges of the minimum cut found from the flow in graph
+pub fn min_cut_capacity(graph: &Graph, sources: &[usize]) -> i64 {
+    graph.min_cut_multi(sources).unwrap().edges.iter().map(|&edge_index| graph.get_edge(edge_index).capacity).sum()
+}
@@ -0,0 +1,145 @@
+use std::io;
+
+use maxflow::dimacs::{parse_dimacs, write_dimacs, write_dimacs_flow, DimacsError, DimacsErrorKind};
+use maxflow::generators::{self, Random};
+use maxflow::{Dinic, Graph, MaxFlowError, MaxFlowSolver};
+
+fn edges(graph: &Graph) -> Vec<(usize, usize, i64)> {
+    (0..graph.num_edges())
+        .map(|edge_index| {
+            let e = graph.get_edge(edge_index);
+            (e.from, e.to, e.capacity)
+        })
+        .collect()
+}
+
+#[test]
+fn round_trip() {
+    let instance = generators::hilo(30, 120, 3, &mut Random::new(7)).unwrap();
+    let mut graph = instance.to_graph();
+    let (source, sink) = (instance.source(), instance.sink());
+    let bipartition = Some((instance.num_left_nodes, instance.num_right_nodes));
+
+    let mut buf = Vec::new();
+    write_dimacs(&mut buf, &graph, source, sink, bipartition).unwrap();
+    let mut problem = parse_dimacs(std::str::from_utf8(&buf).unwrap()).unwrap();
+    assert_eq!(problem.source, source);
+    assert_eq!(problem.sink, sink);
+    assert_eq!(problem.bipartition, bipartition);
+    assert_eq!(problem.graph.num_nodes(), graph.num_nodes());
+    assert_eq!(edges(&problem.graph), edges(&graph));
+
+    let expected = Dinic::new(&mut graph).unwrap().solve(source, sink).unwrap();
+    assert_eq!(Dinic::new(&mut problem.graph).unwrap().solve(source, sink).unwrap(), expected);
+}
+
+#[test]
+fn generated_instances_match_their_file() {
+    let mut rng = Random::new(13);
+    for instance in [generators::hilo(20, 50, 3, &mut rng).unwrap(), generators::zipf(20, 50, 3, &mut rng), generators::rope(20, 50, 3, &mut rng).unwrap()] {
+        let mut buf = Vec::new();
+        instance.write(&mut buf).unwrap();
+        let problem = parse_dimacs(std::str::from_utf8(&buf).unwrap()).unwrap();
+        let graph = instance.to_graph();
+        assert_eq!((problem.source, problem.sink), (instance.source(), instance.sink()));
+        assert_eq!(problem.bipartition, Some((instance.num_left_nodes, instance.num_right_nodes)));
+        assert_eq!(problem.graph.num_nodes(), graph.num_nodes());
+        assert_eq!(edges(&problem.graph), edges(&graph));
+    }
+}
+
+#[test]
+fn flow_output() {
+    let mut graph = Graph::new();
+    graph.add_directed_edge(0, 1, 3);
+    graph.add_directed_edge(1, 2, 2);
+    graph.add_directed_edge(0, 2, 1);
+    let mut solver = Dinic::new(&mut graph).unwrap();
+    let value = solver.solve(0, 2).unwrap();
+
+    let mut buf = Vec::new();
+    write_dimacs_flow(&mut buf, solver.graph(), value).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), "s 3\nf 1 2 2\nf 2 3 2\nf 1 3 1\n");
+}
+
+#[test]
+fn zero_capacity_arcs_are_mapped() {
+    let input = "p max 4 4\nn 1 s\nn 4 t\na 1 2 0\na 1 3 5\na 2 4 0\na 3 4 2\n";
+    let mut problem = parse_dimacs(input).unwrap();
+    assert_eq!(problem.arc_edges, vec![None, Some(0), None, Some(1)]);
+    let value = Dinic::new(&mut problem.graph).unwrap().solve(problem.source, problem.sink).unwrap();
+    assert_eq!(value, 2);
+
+    // the flow of each arc of the file, 0 for those without an edge
+    let flows: Vec<i64> = problem.arc_edges.iter().map(|edge| edge.map_or(0, |edge_index| problem.graph.get_edge(edge_index).flow)).collect();
+    assert_eq!(flows, vec![0, 2, 0, 2]);
+}
+
+#[test]
+fn errors_report_line_and_column() {
+    let cases = [
+        ("c no problem\nn 1 s\n", 2, 1, DimacsErrorKind::MissingProblemLine),
+        ("p max 2 0\np max 2 0\n", 2, 1, DimacsErrorKind::DuplicateProblemLine),
+        ("p min 2 0\n", 1, 3, DimacsErrorKind::UnsupportedProblem("min".to_string())),
+        ("p max 2 0\nx 1 2\n", 2, 1, DimacsErrorKind::UnknownLineType("x".to_string())),
+        ("p max 2 1\na 1 2\n", 2, 6, DimacsErrorKind::MissingField("capacity")),
+        ("p max 2 1\na 1 2 3 5\n", 2, 9, DimacsErrorKind::UnexpectedToken("5".to_string())),
+        ("p max 2 1\na 1 2 x3\n", 2, 7, DimacsErrorKind::InvalidNumber("x3".to_string())),
+        ("p max 2 0\nn 1 q\n", 2, 5, DimacsErrorKind::InvalidTerminal("q".to_string())),
+        ("p max 2 1\na 1 3 4\n", 2, 5, DimacsErrorKind::NodeOutOfRange(3)),
+        ("p max 2 1\n  a 0 2 4\n", 2, 5, DimacsErrorKind::NodeOutOfRange(0)),
+        ("p max 2 1\na 1 2 -4\n", 2, 7, DimacsErrorKind::NegativeCapacity(-4)),
+        ("p max 2 1\na 2 2 4\n", 2, 5, DimacsErrorKind::SelfLoop(2)),
+        ("p max 2 0\nn 1 s\nn 2 s\n", 3, 5, DimacsErrorKind::DuplicateSource),
+        ("p max 2 0\nn 1 t\nn 2 t\n", 3, 5, DimacsErrorKind::DuplicateSink),
+        ("p max 2 0\nn 2 t\n", 1, 1, DimacsErrorKind::MissingSource),
+        ("p max 2 0\nn 1 s\n", 1, 1, DimacsErrorKind::MissingSink),
+        ("p max 2 0\nn 1 s\nn 1 t\n", 1, 1, DimacsErrorKind::SourceIsSink),
+        ("c\np max 2 2\nn 1 s\nn 2 t\na 1 2 3\n", 2, 1, DimacsErrorKind::ArcCountMismatch { expected: 2, found: 1 }),
+    ];
+    for (input, line, column, kind) in cases {
+        assert_eq!(parse_dimacs(input).err(), Some(DimacsError { line, column, kind }), "{:?}", input);
+    }
+}
+
+#[test]
+fn writers_list_the_same_arcs() {
+    let mut graph = Graph::new();
+    graph.add_directed_edge(0, 1, 3);
+    let removed = graph.add_directed_edge(0, 2, 4).unwrap();
+    graph.add_undirected_edge(2, 1, 2);
+    graph.add_directed_edge(2, 3, 5);
+    graph.remove_edge(removed).unwrap();
+    let mut solver = Dinic::new(&mut graph).unwrap();
+    let value = solver.solve(0, 3).unwrap();
+    assert_eq!(value, 2);
+
+    let mut buf = Vec::new();
+    write_dimacs(&mut buf, solver.graph(), 0, 3, None).unwrap();
+    let output = String::from_utf8(buf).unwrap();
+    assert_eq!(output, "p max 4 4\nn 1 s\nn 4 t\na 1 2 3\na 2 3 2\na 3 2 2\na 3 4 5\n");
+    let mut buf = Vec::new();
+    write_dimacs_flow(&mut buf, solver.graph(), value).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), "s 2\nf 1 2 2\nf 2 3 2\nf 3 2 0\nf 3 4 2\n");
+
+    // the arcs read back have the same max flow
+    let mut problem = parse_dimacs(&output).unwrap();
+    assert_eq!(Dinic::new(&mut problem.graph).unwrap().solve(0, 3).unwrap(), value);
+}
+
+#[test]
+fn unsupported_graphs_are_not_written() {
+    let mut graph = Graph::new();
+    graph.add_directed_edge(0, 1, 3);
+    graph.add_directed_edge(1, 2, 3);
+    graph.set_node_capacity(1, 1).unwrap();
+    let error = write_dimacs(&mut Vec::new(), &graph, 0, 2, None).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(error.into_inner().unwrap().downcast_ref(), Some(&MaxFlowError::NodeCapacityUnsupported { node: 1 }));
+
+    let mut graph = Graph::new();
+    graph.add_directed_edge(0, 1, 3);
+    graph.add_directed_edge_with_bounds(1, 2, 1, 3);
+    let error = write_dimacs_flow(&mut Vec::new(), &graph, 0).unwrap_err();
+    assert_eq!(error.into_inner().unwrap().downcast_ref(), Some(&MaxFlowError::LowerBoundUnsupported { edge_index: 1 }));
+}
@@ -0,0 +1,86 @@
+use maxflow::{Dinic, Graph, MaxFlowError, MaxFlowSolver};
+
+// a path 0 -> 1 -> 2 with capacities 3 and 2
+fn path() -> Graph {
+    let mut graph = Graph::new();
+    graph.add_directed_edge(0, 1, 3);
+    graph.add_directed_edge(1, 2, 2);
+    graph
+}
+
+// set the flow on the inside edge u -> v of a built graph, and the flow of its reverse edge to match
+fn set_inside_flow(graph: &mut Graph, u: usize, v: usize, flow: i64) {
+    let i = (graph.start[u]..graph.start[u + 1]).find(|&i| graph.inside_edge_list[i].to == v).unwrap();
+    let rev = graph.inside_edge_list[i].rev;
+    graph.inside_edge_list[i].flow = flow;
+    graph.inside_edge_list[rev].flow = graph.inside_edge_list[rev].capacity - flow;
+}
+
+#[test]
+fn self_loop_is_rejected_at_build() {
+    let mut graph = path();
+    let edge_index = graph.add_directed_edge(1, 1, 4).unwrap();
+    assert_eq!(graph.build(), Err(MaxFlowError::SelfLoop { edge_index, node: 1 }));
+    assert_eq!(Dinic::new(&mut graph).err(), Some(MaxFlowError::SelfLoop { edge_index, node: 1 }));
+}
+
+#[test]
+fn source_is_sink_is_rejected_on_solve() {
+    let mut graph = path();
+    assert_eq!(Dinic::new(&mut graph).unwrap().solve(1, 1), Err(MaxFlowError::SourceIsSink { node: 1 }));
+    assert_eq!(Dinic::new(&mut graph).unwrap().solve_multi(&[0, 1], &[1, 2]), Err(MaxFlowError::SourceIsSink { node: 1 }));
+}
+
+#[test]
+fn negative_capacity_is_rejected() {
+    let mut graph = path();
+    assert_eq!(graph.add_directed_edge(0, 2, -1), None);
+    assert_eq!(graph.set_capacity(0, -1), Err(MaxFlowError::NegativeCapacity { edge_index: 0 }));
+    let mut solver = Dinic::new(&mut graph).unwrap();
+    assert_eq!(solver.solve(0, 2), Ok(2));
+    assert_eq!(solver.set_capacity(1, -5), Err(MaxFlowError::NegativeCapacity { edge_index: 1 }));
+}
+
+#[test]
+fn removed_edge_capacity_cannot_be_set() {
+    let mut graph = path();
+    graph.remove_edge(1).unwrap();
+    assert_eq!(graph.set_capacity(1, 4), Err(MaxFlowError::EdgeRemoved { edge_index: 1 }));
+    assert_eq!(Dinic::new(&mut graph).unwrap().solve(0, 2), Ok(0));
+    assert_eq!(graph.set_capacity(1, 4), Err(MaxFlowError::EdgeRemoved { edge_index: 1 }));
+}
+
+#[test]
+fn edge_out_of_range_is_rejected() {
+    let mut graph = path();
+    assert_eq!(graph.set_capacity(2, 4), Err(MaxFlowError::EdgeOutOfRange { edge_index: 2, num_edges: 2 }));
+    assert_eq!(graph.remove_edge(5), Err(MaxFlowError::EdgeOutOfRange { edge_index: 5, num_edges: 2 }));
+    let mut solver = Dinic::new(&mut graph).unwrap();
+    assert_eq!(solver.solve(0, 2), Ok(2));
+    assert_eq!(solver.set_capacity(2, 4), Err(MaxFlowError::EdgeOutOfRange { edge_index: 2, num_edges: 2 }));
+    assert_eq!(solver.graph_mut().remove_edge(2), Err(MaxFlowError::EdgeOutOfRange { edge_index: 2, num_edges: 2 }));
+}
+
+#[test]
+fn undirected_edge_at_split_node_is_rejected() {
+    let mut graph = path();
+    let edge_index = graph.add_undirected_edge(1, 2, 4).unwrap();
+    graph.set_node_capacity(2, 1).unwrap();
+    assert_eq!(graph.build(), Err(MaxFlowError::UndirectedEdgeAtSplitNode { edge_index, node: 2 }));
+}
+
+#[test]
+fn validation_reports_a_corrupted_flow() {
+    let mut graph = path();
+    graph.set_validation(true);
+    assert_eq!(Dinic::new(&mut graph).unwrap().solve(0, 2), Ok(2));
+
+    // more flow enters node 1 than leaves it
+    set_inside_flow(&mut graph, 0, 1, 3);
+    assert_eq!(Dinic::new(&mut graph).unwrap().solve(0, 2), Err(MaxFlowError::ConservationViolation { node: 1 }));
+
+    // more flow than capacity, which the solvers assume cannot happen, so it is only checked on the graph
+    set_inside_flow(&mut graph, 0, 1, 2);
+    set_inside_flow(&mut graph, 1, 2, 4);
+    assert_eq!(graph.validate_flow(&[0], &[2]), Err(MaxFlowError::CapacityViolation { edge_index: 1 }));
+}
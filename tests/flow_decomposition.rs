@@ -0,0 +1,60 @@
+use maxflow::generators::{self, Random};
+use maxflow::{decompose_flow, BipartitePushRelabelFIFO, BipartitePushRelabelHighestLabel, Dinic, FlowPath, Graph, MaxFlowSolver};
+
+fn new_solver(algorithm: usize, num_left_nodes: usize, num_right_nodes: usize, graph: &mut Graph) -> Box<dyn MaxFlowSolver + '_> {
+    match algorithm {
+        0 => Box::new(Dinic::new(graph).unwrap()),
+        1 => Box::new(BipartitePushRelabelFIFO::new(num_left_nodes, num_right_nodes, graph).unwrap()),
+        _ => Box::new(BipartitePushRelabelHighestLabel::new(num_left_nodes, num_right_nodes, graph).unwrap()),
+    }
+}
+
+// the edges follow each other, from start to end
+fn assert_walk(graph: &Graph, path: &FlowPath, start: usize, end: usize) {
+    assert!(!path.edges.is_empty() && path.flow > 0);
+    let mut u = start;
+    for &edge_index in path.edges.iter() {
+        let e = graph.get_edge(edge_index);
+        assert_eq!(e.from, u);
+        u = e.to;
+    }
+    assert_eq!(u, end);
+}
+
+#[test]
+fn paths_and_cycles_add_up_to_the_flow() {
+    let mut rng = Random::new(23);
+    for round in 0..30 {
+        let n1 = rng.gen_range(1, 20) as usize;
+        let (n2, d) = (rng.gen_range(n1 as u64, 60) as usize, rng.gen_range(1, 5) as usize);
+        let instance = match round % 3 {
+            0 => generators::hilo(n1, n2, d, &mut rng).unwrap(),
+            1 => generators::zipf(n1, n2, d, &mut rng),
+            _ => match generators::rope(n1, n2, d, &mut rng) {
+                Some(instance) => instance,
+                None => continue,
+            },
+        };
+        let (source, sink) = (instance.source(), instance.sink());
+        for algorithm in 0..3 {
+            let mut graph = instance.to_graph();
+            let value = new_solver(algorithm, n1, n2, &mut graph).solve(source, sink).unwrap();
+            let decomposition = decompose_flow(&graph, source, sink);
+
+            assert_eq!(decomposition.paths.iter().map(|path| path.flow).sum::<i64>(), value, "algorithm {}", algorithm);
+            let mut flows = vec![0; graph.num_edges()];
+            for path in decomposition.paths.iter().chain(decomposition.cycles.iter()) {
+                path.edges.iter().for_each(|&edge_index| flows[edge_index] += path.flow);
+            }
+            for (edge_index, &flow) in flows.iter().enumerate() {
+                assert_eq!(flow, graph.get_edge(edge_index).flow, "algorithm {} edge {}", algorithm, edge_index);
+            }
+            decomposition.paths.iter().for_each(|path| assert_walk(&graph, path, source, sink));
+            for cycle in decomposition.cycles.iter() {
+                let start = graph.get_edge(cycle.edges[0]).from;
+                assert_walk(&graph, cycle, start, start);
+            }
+            assert!(decomposition.paths.len() + decomposition.cycles.len() <= graph.num_edges());
+        }
+    }
+}
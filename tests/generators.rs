@@ -0,0 +1,16 @@
+use maxflow::generators::{self, Random};
+
+#[test]
+fn degenerate_sizes_return_none() {
+    let mut rng = Random::new(1);
+    assert!(generators::hilo(0, 0, 2, &mut rng).is_none());
+    assert!(generators::hilo(0, 10, 2, &mut rng).is_none());
+    assert!(generators::rope(10, 20, 0, &mut rng).is_none());
+    assert!(generators::rope(1, 20, 2, &mut rng).is_none());
+    assert!(generators::rope(10, 2, 2, &mut rng).is_none());
+
+    let instance = generators::hilo(1, 0, 2, &mut rng).unwrap();
+    assert_eq!(instance.to_graph().num_nodes(), 3);
+    let instance = generators::rope(10, 5, 2, &mut rng).unwrap();
+    assert_eq!((instance.num_left_nodes, instance.num_right_nodes), (10, 5));
+}
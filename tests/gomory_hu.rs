@@ -0,0 +1,50 @@
+mod common;
+
+use common::cuts::{all_cuts, cut_capacity, random_symmetric_graph};
+use maxflow::generators::Random;
+use maxflow::{BoykovKolmogorov, Dinic, GomoryHuTree, Graph, MaxFlowError, MaxFlowSolver, Pseudoflow, PushRelabelFIFO};
+
+fn new_solver(algorithm: usize, graph: &mut Graph) -> Box<dyn MaxFlowSolver + '_> {
+    match algorithm {
+        0 => Box::new(Dinic::new(graph).unwrap()),
+        1 => Box::new(PushRelabelFIFO::new(graph).unwrap()),
+        2 => Box::new(Pseudoflow::new(graph).unwrap()),
+        _ => Box::new(BoykovKolmogorov::new(graph).unwrap()),
+    }
+}
+
+#[test]
+fn pair_cuts_match_brute_force() {
+    let mut rng = Random::new(67);
+    for round in 0..120 {
+        let n = rng.gen_range(2, 7) as usize;
+        let mut graph = random_symmetric_graph(&mut rng, n);
+        let cuts = all_cuts(&graph);
+        let tree = GomoryHuTree::new(new_solver(round % 4, &mut graph).as_mut()).unwrap();
+        assert_eq!(tree.num_nodes(), n);
+        assert_eq!(tree.edges().len(), n - 1);
+
+        for u in 0..n {
+            assert_eq!(tree.min_cut_value(u, u), i64::MAX);
+            assert!(tree.min_cut(u, u).is_empty());
+            for v in (0..n).filter(|&v| v != u) {
+                let want = cuts.iter().filter(|&&(mask, _)| mask >> u & 1 == 1 && mask >> v & 1 == 0).map(|&(_, capacity)| capacity).min().unwrap();
+                assert_eq!(tree.min_cut_value(u, v), want, "round {} pair {} {}", round, u, v);
+
+                let side = tree.min_cut(u, v);
+                let mut in_side = vec![false; n];
+                side.iter().for_each(|&x| in_side[x] = true);
+                assert!(in_side[u] && !in_side[v]);
+                assert_eq!(cut_capacity(&graph, &in_side), want);
+            }
+        }
+    }
+}
+
+#[test]
+fn asymmetric_capacities_are_rejected() {
+    let mut graph = Graph::new();
+    graph.add_directed_edge(0, 1, 3);
+    graph.add_directed_edge(1, 0, 2);
+    assert_eq!(GomoryHuTree::new(&mut Dinic::new(&mut graph).unwrap()), Err(MaxFlowError::AsymmetricCapacity { edge_index: 0 }));
+}
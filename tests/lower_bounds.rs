@@ -0,0 +1,90 @@
+use maxflow::generators::Random;
+use maxflow::{max_flow_with_lower_bounds, Feasibility, Graph};
+
+// largest value of an integral flow from 0 to n - 1 within the bounds by enumeration, None if there is none
+fn brute_force(n: usize, edges: &[(usize, usize, i64, i64)]) -> Option<i64> {
+    fn assign(k: usize, n: usize, edges: &[(usize, usize, i64, i64)], balance: &mut Vec<i64>, best: &mut Option<i64>) {
+        if k == edges.len() {
+            if balance[1..n - 1].iter().all(|&b| b == 0) && balance[n - 1] >= 0 {
+                *best = (*best).max(Some(balance[n - 1]));
+            }
+            return;
+        }
+        let (u, v, lower, upper) = edges[k];
+        for flow in lower..=upper {
+            balance[u] -= flow;
+            balance[v] += flow;
+            assign(k + 1, n, edges, balance, best);
+            balance[u] += flow;
+            balance[v] -= flow;
+        }
+    }
+    let mut best = None;
+    assign(0, n, edges, &mut vec![0; n], &mut best);
+    best
+}
+
+#[test]
+fn matches_brute_force_with_certificates() {
+    let mut rng = Random::new(61);
+    let (mut feasible, mut infeasible) = (0, 0);
+    for _ in 0..400 {
+        let n = rng.gen_range(2, 5) as usize;
+        let mut edges = vec![];
+        let mut graph = Graph::with_num_nodes(n);
+        for _ in 0..rng.gen_range(1, 6) {
+            let (u, v) = (rng.gen_range(0, n as u64 - 1) as usize, rng.gen_range(0, n as u64 - 1) as usize);
+            let (lower, upper) = (rng.gen_range(0, 2) as i64, rng.gen_range(1, 3) as i64);
+            if u != v && lower <= upper {
+                graph.add_directed_edge_with_bounds(u, v, lower, upper).unwrap();
+                edges.push((u, v, lower, upper));
+            }
+        }
+        graph.set_validation(true);
+
+        match (max_flow_with_lower_bounds(&mut graph, 0, n - 1).unwrap(), brute_force(n, &edges)) {
+            (Feasibility::Feasible(value), Some(want)) => {
+                feasible += 1;
+                assert_eq!(value, want, "{:?}", edges);
+                for (edge_index, &(_, _, lower, upper)) in edges.iter().enumerate() {
+                    let flow = graph.get_edge(edge_index).flow;
+                    assert!(lower <= flow && flow <= upper);
+                }
+            }
+            (Feasibility::Infeasible(cut), None) => {
+                infeasible += 1;
+                // the lower bounds entering the source side are more than can leave it,
+                // and with the sink on the source side the source is too, so the flow could not make up for it
+                let in_source_side = |u: usize| cut.source_side.contains(&u);
+                assert!(!in_source_side(n - 1) || in_source_side(0), "{:?}", edges);
+                let lower_in: i64 = edges.iter().filter(|e| !in_source_side(e.0) && in_source_side(e.1)).map(|e| e.2).sum();
+                let mut leaving: Vec<usize> = (0..edges.len()).filter(|&i| in_source_side(edges[i].0) && !in_source_side(edges[i].1)).collect();
+                leaving.sort_unstable();
+                let mut cut_edges = cut.edges.clone();
+                cut_edges.sort_unstable();
+                assert_eq!(cut_edges, leaving);
+                let capacity_out: i64 = cut.edges.iter().map(|&edge_index| edges[edge_index].3).sum();
+                assert!(lower_in > capacity_out, "{:?} {:?}", edges, cut);
+            }
+            (result, want) => panic!("{:?} gives {:?}, brute force {:?}", edges, result, want),
+        }
+    }
+    assert!(feasible > 50 && infeasible > 50, "{} {}", feasible, infeasible);
+}
+
+#[test]
+fn infeasible_through_a_node_capacity() {
+    // the lower bounds push 3 through node 1, whose capacity is 2
+    let mut graph = Graph::new();
+    graph.add_directed_edge_with_bounds(0, 1, 3, 5);
+    graph.add_directed_edge_with_bounds(1, 2, 0, 5);
+    graph.set_node_capacity(1, 2).unwrap();
+    match max_flow_with_lower_bounds(&mut graph, 0, 2).unwrap() {
+        Feasibility::Infeasible(cut) => {
+            assert!(cut.source_side.contains(&1) && !cut.source_side.contains(&2));
+            assert_eq!(cut.nodes, vec![1]);
+            assert!(cut.edges.is_empty());
+        }
+        feasible => panic!("{:?}", feasible),
+    }
+}
@@ -0,0 +1,110 @@
+use maxflow::generators::Random;
+use maxflow::{Graph, MaxFlowError, MinCostFlow};
+
+// every integral flow from 0 to n - 1 by enumeration, as (value, cost)
+fn all_flows(n: usize, edges: &[(usize, usize, i64, i64)]) -> Vec<(i64, i64)> {
+    fn assign(k: usize, n: usize, edges: &[(usize, usize, i64, i64)], balance: &mut Vec<i64>, cost: i64, flows: &mut Vec<(i64, i64)>) {
+        if k == edges.len() {
+            if balance[1..n - 1].iter().all(|&b| b == 0) {
+                flows.push((balance[n - 1], cost));
+            }
+            return;
+        }
+        let (u, v, capacity, c) = edges[k];
+        for flow in 0..=capacity {
+            balance[u] -= flow;
+            balance[v] += flow;
+            assign(k + 1, n, edges, balance, cost + c * flow, flows);
+            balance[u] += flow;
+            balance[v] -= flow;
+        }
+    }
+    let mut flows = vec![];
+    assign(0, n, edges, &mut vec![0; n], 0, &mut flows);
+    flows
+}
+
+// edges from a smaller node to a larger one, so that there is no cycle, with costs of either sign
+fn random_dag(rng: &mut Random, n: usize) -> Vec<(usize, usize, i64, i64)> {
+    let mut edges = vec![];
+    for _ in 0..rng.gen_range(1, 7) {
+        let (u, v) = (rng.gen_range(0, n as u64 - 1) as usize, rng.gen_range(0, n as u64 - 1) as usize);
+        if u < v {
+            edges.push((u, v, rng.gen_range(1, 3) as i64, rng.gen_range(0, 10) as i64 - 5));
+        }
+    }
+    edges
+}
+
+fn new_graph(n: usize, edges: &[(usize, usize, i64, i64)]) -> Graph {
+    let mut graph = Graph::with_num_nodes(n);
+    for &(u, v, capacity, cost) in edges {
+        graph.add_directed_edge_with_cost(u, v, capacity, cost);
+    }
+    graph
+}
+
+// the flow left in the graph is a flow of the reported value and cost
+fn check_flow(graph: &Graph, n: usize, (value, cost): (i64, i64)) {
+    let mut balance = vec![0; n];
+    let mut total_cost = 0;
+    for edge_index in 0..graph.num_edges() {
+        let e = graph.get_edge(edge_index);
+        assert!(0 <= e.flow && e.flow <= e.capacity);
+        balance[e.from] -= e.flow;
+        balance[e.to] += e.flow;
+        total_cost += e.cost * e.flow;
+    }
+    assert!(balance[1..n - 1].iter().all(|&b| b == 0));
+    assert_eq!((balance[n - 1], total_cost), (value, cost));
+}
+
+#[test]
+fn negative_costs_match_brute_force() {
+    let mut rng = Random::new(53);
+    for _ in 0..300 {
+        let n = rng.gen_range(2, 5) as usize;
+        let edges = random_dag(&mut rng, n);
+        let flows = all_flows(n, &edges);
+        let max_value = flows.iter().map(|&(value, _)| value).max().unwrap();
+        let min_cost_of = |value: i64| flows.iter().filter(|f| f.0 == value).map(|f| f.1).min().unwrap();
+
+        let mut graph = new_graph(n, &edges);
+        let mut solver = MinCostFlow::new(&mut graph).unwrap();
+        let result = solver.solve(0, n - 1).unwrap();
+        assert_eq!(result, (max_value, min_cost_of(max_value)), "{:?}", edges);
+        check_flow(solver.graph, n, result);
+
+        for limit in 0..max_value {
+            let result = solver.solve_with_flow_limit(0, n - 1, limit).unwrap();
+            assert_eq!(result, (limit, min_cost_of(limit)), "{:?} limit {}", edges, limit);
+            check_flow(solver.graph, n, result);
+        }
+
+        // the cheapest flow of any value
+        let result = solver.solve_min_cost(0, n - 1).unwrap();
+        assert_eq!(result.1, flows.iter().map(|f| f.1).min().unwrap(), "{:?}", edges);
+        check_flow(solver.graph, n, result);
+    }
+}
+
+#[test]
+fn negative_cycle_is_reported() {
+    let mut graph = Graph::new();
+    graph.add_directed_edge_with_cost(0, 1, 1, 1);
+    graph.add_directed_edge_with_cost(1, 2, 1, -3);
+    graph.add_directed_edge_with_cost(2, 1, 1, 1);
+    graph.add_directed_edge_with_cost(2, 3, 1, 1);
+    assert!(matches!(MinCostFlow::new(&mut graph).unwrap().solve(0, 3), Err(MaxFlowError::NegativeCostCycle { .. })));
+}
+
+#[test]
+fn large_costs_report_overflow() {
+    // the path cost overflows in dijkstra with nonnegative costs, and in the Bellman-Ford with negative ones
+    for cost in [i64::MAX / 2 + 1, i64::MIN / 2 - 1] {
+        let mut graph = Graph::new();
+        graph.add_directed_edge_with_cost(0, 1, 1, cost);
+        graph.add_directed_edge_with_cost(1, 2, 1, cost);
+        assert_eq!(MinCostFlow::new(&mut graph).unwrap().solve(0, 2), Err(MaxFlowError::CostOverflow));
+    }
+}
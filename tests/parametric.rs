@@ -0,0 +1,107 @@
+use maxflow::generators::Random;
+use maxflow::{Dinic, Graph, ParametricMaxFlow};
+
+// source 0, left nodes 1..=n1, right nodes after them and sink last
+// the source edges grow with lambda, the sink edges shrink to a capacity of at least 0 at lambda = 10
+struct Instance {
+    n1: usize,
+    n2: usize,
+    edges: Vec<(usize, usize, f64, f64)>, // (from, to, constant, slope)
+}
+
+impl Instance {
+    fn random(rng: &mut Random) -> Self {
+        let (n1, n2) = (rng.gen_range(1, 6) as usize, rng.gen_range(6, 10) as usize);
+        let sink = n1 + n2 + 1;
+        let mut edges = vec![];
+        for u in 1..=n1 {
+            edges.push((0, u, rng.gen_range(0, 5) as f64, rng.gen_range(0, 3) as f64));
+            for v in n1 + 1..=n1 + n2 {
+                if rng.gen_range(0, 2) == 0 {
+                    edges.push((u, v, rng.gen_range(1, 8) as f64, 0.0));
+                }
+            }
+        }
+        for v in n1 + 1..=n1 + n2 {
+            let slope = rng.gen_range(0, 2) as f64;
+            edges.push((v, sink, rng.gen_range(0, 4) as f64 + 10.0 * slope, -slope));
+        }
+        Instance { n1, n2, edges }
+    }
+
+    fn sink(&self) -> usize {
+        self.n1 + self.n2 + 1
+    }
+
+    // the max flow at lambda on a graph of its own
+    fn solve(&self, lambda: f64) -> f64 {
+        let mut graph = Graph::<f64>::with_num_nodes(self.sink() + 1);
+        for &(u, v, constant, slope) in self.edges.iter() {
+            graph.add_directed_edge(u, v, (constant + slope * lambda).max(0.0));
+        }
+        Dinic::new(&mut graph).unwrap().solve(0, self.sink()).unwrap()
+    }
+
+    fn parametric<'a>(&self, graph: &'a mut Graph<f64>) -> ParametricMaxFlow<'a> {
+        // a terminal edge may have capacity 0 at some lambda, which add_directed_edge refuses, so it starts at 1
+        let edge_indices: Vec<usize> = self
+            .edges
+            .iter()
+            .map(|&(u, v, constant, _)| graph.add_directed_edge(u, v, if u == 0 || v == self.sink() { 1.0 } else { constant }).unwrap())
+            .collect();
+        let mut parametric = ParametricMaxFlow::new(self.n1, self.n2, graph, 0, self.sink()).unwrap();
+        for (&(u, v, constant, slope), edge_index) in self.edges.iter().zip(edge_indices) {
+            if u == 0 || v == self.sink() {
+                parametric.set_parametric_capacity(edge_index, constant, slope).unwrap();
+            }
+        }
+        parametric
+    }
+}
+
+#[test]
+fn solve_matches_independent_solves() {
+    let mut rng = Random::new(43);
+    for _ in 0..30 {
+        let instance = Instance::random(&mut rng);
+        let mut graph = Graph::<f64>::with_num_nodes(instance.sink() + 1);
+        let mut parametric = instance.parametric(&mut graph);
+        // growing lambda continues from the last preflow, and a smaller one starts again
+        for lambda in (0..=40).map(|k| k as f64 * 0.25).chain([3.0, 0.5, 7.25]) {
+            let want = instance.solve(lambda);
+            assert!((parametric.solve(lambda).unwrap() - want).abs() < 1e-9, "lambda {}", lambda);
+        }
+    }
+}
+
+#[test]
+fn breakpoints_match_a_grid_of_solves() {
+    let mut rng = Random::new(47);
+    let (low, high) = (0.0, 10.0);
+    for _ in 0..30 {
+        let instance = Instance::random(&mut rng);
+        let mut graph = Graph::<f64>::with_num_nodes(instance.sink() + 1);
+        let breakpoints = instance.parametric(&mut graph).breakpoints(low, high).unwrap();
+        assert!(breakpoints.windows(2).all(|w| w[0] < w[1]) && breakpoints.iter().all(|&b| low < b && b < high));
+
+        // the max flow is concave and piecewise linear in lambda, linear between two breakpoints,
+        // so three grid points between the same two breakpoints are on a line
+        let segment = |lambda: f64| breakpoints.iter().filter(|&&b| b <= lambda).count();
+        let grid: Vec<f64> = (0..=80).map(|k| k as f64 * 0.125).collect();
+        let values: Vec<f64> = grid.iter().map(|&lambda| instance.solve(lambda)).collect();
+        for k in 1..grid.len() - 1 {
+            if segment(grid[k - 1]) == segment(grid[k + 1]) && !breakpoints.contains(&grid[k - 1]) {
+                assert!((values[k - 1] + values[k + 1] - 2.0 * values[k]).abs() < 1e-9, "no breakpoint near {}", grid[k]);
+            }
+        }
+        // and the slope changes at every breakpoint
+        let mut ends = vec![low];
+        ends.extend(breakpoints.iter());
+        ends.push(high);
+        for i in 1..ends.len() - 1 {
+            let delta = (ends[i] - ends[i - 1]).min(ends[i + 1] - ends[i]) / 2.0;
+            let (before, at, after) = (instance.solve(ends[i] - delta), instance.solve(ends[i]), instance.solve(ends[i] + delta));
+            assert!(at - (before + after) / 2.0 > 1e-9, "no kink at {}", ends[i]);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::{dinic, min_cut_capacity, random_graph};
+use maxflow::generators::{self, Random};
+use maxflow::{MaxFlowSolver, Pseudoflow};
+
+#[test]
+fn matches_dinic_on_random_graphs() {
+    let mut rng = Random::new(11);
+    for round in 0..200 {
+        let n = rng.gen_range(4, 30) as usize;
+        let mut graph = random_graph(&mut rng, n, 120, false);
+        let (sources, sinks) = if round % 2 == 0 { (vec![0], vec![n - 1]) } else { (vec![0, 1], vec![n - 2, n - 1]) };
+        let want = dinic(&graph, &sources, &sinks);
+        graph.set_validation(true);
+        let mut solver = Pseudoflow::new(&mut graph).unwrap();
+        assert_eq!(solver.solve_multi(&sources, &sinks).unwrap(), want);
+        assert_eq!(min_cut_capacity(solver.graph(), &sources), want);
+    }
+}
+
+#[test]
+fn matches_dinic_on_bipartite_instances() {
+    let mut rng = Random::new(13);
+    for instance in [generators::hilo(30, 60, 5, &mut rng).unwrap(), generators::zipf(30, 60, 5, &mut rng), generators::rope(30, 60, 5, &mut rng).unwrap()] {
+        let (source, sink) = (instance.source(), instance.sink());
+        let mut graph = instance.to_graph();
+        let want = dinic(&graph, &[source], &[sink]);
+        graph.set_validation(true);
+        let mut solver = Pseudoflow::new(&mut graph).unwrap();
+        assert_eq!(solver.solve(source, sink).unwrap(), want);
+
+        // warm start after lowering the capacities of some edges
+        for edge_index in (0..solver.graph().num_edges()).step_by(7) {
+            solver.set_capacity(edge_index, 1).unwrap();
+        }
+        let want = dinic(solver.graph(), &[source], &[sink]);
+        assert_eq!(solver.solve(source, sink).unwrap(), want);
+    }
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::{dinic, min_cut_capacity, random_graph};
+use maxflow::generators::{self, Random};
+use maxflow::{BipartitePushRelabelFIFO, BipartitePushRelabelHighestLabel, Graph, MaxFlowError, MaxFlowSolver, PushRelabelFIFO, PushRelabelHighestLabel};
+
+fn new_solver(algorithm: usize, graph: &mut Graph) -> Box<dyn MaxFlowSolver + '_> {
+    match algorithm {
+        0 => Box::new(PushRelabelFIFO::new(graph).unwrap()),
+        1 => Box::new(PushRelabelHighestLabel::new(graph).unwrap()),
+        2 => {
+            let mut solver = PushRelabelFIFO::new(graph).unwrap();
+            solver.set_alpha(0);
+            Box::new(solver)
+        }
+        _ => {
+            let mut solver = PushRelabelHighestLabel::new(graph).unwrap();
+            solver.set_alpha(0);
+            Box::new(solver)
+        }
+    }
+}
+
+#[test]
+fn matches_dinic_on_random_graphs() {
+    let mut rng = Random::new(17);
+    for round in 0..400 {
+        let n = rng.gen_range(4, 30) as usize;
+        let mut graph = random_graph(&mut rng, n, 120, true);
+        let (sources, sinks) = if round % 2 == 0 { (vec![0], vec![n - 1]) } else { (vec![0, 1], vec![n - 2, n - 1]) };
+        let want = dinic(&graph, &sources, &sinks);
+        graph.set_validation(true);
+        let mut solver = new_solver(round % 4, &mut graph);
+        assert_eq!(solver.solve_multi(&sources, &sinks).unwrap(), want);
+        assert_eq!(min_cut_capacity(solver.graph(), &sources), want);
+    }
+}
+
+#[test]
+fn node_capacities_and_warm_start() {
+    let mut rng = Random::new(19);
+    for round in 0..100 {
+        let n = rng.gen_range(4, 20) as usize;
+        let mut graph = random_graph(&mut rng, n, 80, false);
+        graph.set_node_capacity(rng.gen_range(1, n as u64 - 2) as usize, rng.gen_range(0, 150) as i64).unwrap();
+        let want = dinic(&graph, &[0], &[n - 1]);
+        graph.set_validation(true);
+        let mut solver = new_solver(round % 4, &mut graph);
+        assert_eq!(solver.solve(0, n - 1).unwrap(), want);
+
+        // the second solve starts from the flow of the first one
+        for edge_index in (0..solver.graph().num_edges()).step_by(3) {
+            solver.set_capacity(edge_index, rng.gen_range(0, 100) as i64).unwrap();
+        }
+        let want = dinic(solver.graph(), &[0], &[n - 1]);
+        assert_eq!(solver.solve(0, n - 1).unwrap(), want);
+    }
+}
+
+#[test]
+fn bipartite_global_update_matches_dinic() {
+    let mut rng = Random::new(23);
+    for round in 0..300 {
+        let (n1, d) = (rng.gen_range(1, 12) as usize, rng.gen_range(1, 6) as usize);
+        let n2 = n1 + rng.gen_range(0, 30) as usize;
+        let instance = match round % 3 {
+            0 => generators::hilo(n1, n2, d, &mut rng).unwrap(),
+            1 => generators::zipf(n1, n2, d, &mut rng),
+            _ => match generators::rope(n1, n2, d, &mut rng) {
+                Some(instance) => instance,
+                None => continue,
+            },
+        };
+        let (source, sink) = (instance.source(), instance.sink());
+        let want = dinic(&instance.to_graph(), &[source], &[sink]);
+        for alpha in 1..3 {
+            let mut graph = instance.to_graph();
+            graph.set_validation(true);
+            let mut fifo = BipartitePushRelabelFIFO::with_detected_bipartition(&mut graph, source, sink).unwrap();
+            fifo.set_alpha(alpha);
+            assert_eq!(fifo.solve(source, sink).unwrap(), want);
+
+            let mut graph = instance.to_graph();
+            graph.set_validation(true);
+            let mut highest_label = BipartitePushRelabelHighestLabel::with_detected_bipartition(&mut graph, source, sink).unwrap();
+            highest_label.set_alpha(alpha);
+            assert_eq!(highest_label.solve(source, sink).unwrap(), want);
+        }
+    }
+}
+
+#[test]
+fn bipartite_sides_are_checked() {
+    // the inner edges 1 -> 2 and 3 -> 4 need a left node each
+    let mut graph = Graph::new();
+    for (u, v) in [(0, 1), (1, 2), (2, 5), (0, 3), (3, 4), (4, 5)] {
+        graph.add_directed_edge(u, v, 5);
+    }
+    assert_eq!(BipartitePushRelabelFIFO::new(1, 3, &mut graph).unwrap().solve(0, 5), Err(MaxFlowError::InvalidBipartition { num_left_nodes: 1, num_right_nodes: 3 }));
+    assert!(BipartitePushRelabelHighestLabel::new(1, 3, &mut graph).unwrap().solve(0, 5).is_err());
+    assert_eq!(BipartitePushRelabelFIFO::new(2, 2, &mut graph).unwrap().solve(0, 5), Ok(10));
+
+    // a triangle of inner nodes is not bipartite for any sides
+    graph.add_directed_edge(2, 3, 5);
+    graph.add_directed_edge(3, 1, 5);
+    assert!(matches!(BipartitePushRelabelHighestLabel::new(2, 2, &mut graph).unwrap().solve(0, 5), Err(MaxFlowError::NotBipartite { .. })));
+
+    // sides that are known to be right skip the check
+    let instance = generators::hilo(10, 40, 3, &mut Random::new(41)).unwrap();
+    let (source, sink) = (instance.source(), instance.sink());
+    let want = dinic(&instance.to_graph(), &[source], &[sink]);
+    let mut graph = instance.to_graph();
+    let mut solver = BipartitePushRelabelHighestLabel::new_unchecked(instance.num_left_nodes, instance.num_right_nodes, &mut graph).unwrap();
+    assert_eq!(solver.solve(source, sink).unwrap(), want);
+}
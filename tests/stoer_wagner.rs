@@ -0,0 +1,45 @@
+mod common;
+
+use common::cuts::{all_cuts, cut_capacity, random_symmetric_graph};
+use maxflow::generators::Random;
+use maxflow::{stoer_wagner, Graph, MaxFlowError};
+
+#[test]
+fn matches_brute_force_on_random_graphs() {
+    let mut rng = Random::new(71);
+    for round in 0..300 {
+        let n = rng.gen_range(2, 8) as usize;
+        let graph = random_symmetric_graph(&mut rng, n);
+        let full = (1u32 << n) - 1;
+        let want = all_cuts(&graph).into_iter().filter(|&(mask, _)| mask != 0 && mask != full).map(|(_, capacity)| capacity).min().unwrap();
+
+        let (value, cut) = stoer_wagner(&graph).unwrap();
+        assert_eq!(value, want, "round {}", round);
+        let mut in_side = vec![false; n];
+        cut.source_side.iter().for_each(|&u| in_side[u] = true);
+        assert!(!cut.source_side.is_empty() && cut.source_side.len() < n);
+        assert_eq!(cut_capacity(&graph, &in_side), want);
+    }
+}
+
+#[test]
+fn less_than_two_nodes_have_no_cut() {
+    for n in 0..2 {
+        let (value, cut) = stoer_wagner(&Graph::<i64>::with_num_nodes(n)).unwrap();
+        assert_eq!(value, i64::MAX);
+        assert!(cut.source_side.is_empty());
+    }
+}
+
+#[test]
+fn asymmetric_capacities_and_node_capacities_are_rejected() {
+    let mut graph = Graph::with_num_nodes(3);
+    graph.add_directed_edge(0, 1, 3);
+    graph.add_directed_edge(1, 0, 2);
+    assert_eq!(stoer_wagner(&graph).unwrap_err(), MaxFlowError::AsymmetricCapacity { edge_index: 0 });
+
+    let mut graph = Graph::with_num_nodes(3);
+    graph.add_undirected_edge(0, 1, 3);
+    graph.set_node_capacity(2, 1).unwrap();
+    assert_eq!(stoer_wagner(&graph).unwrap_err(), MaxFlowError::NodeCapacityUnsupported { node: 2 });
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use common::{dinic, random_graph};
+use maxflow::generators::{self, Random};
+use maxflow::{
+    BipartitePushRelabelFIFO, BipartitePushRelabelHighestLabel, BoykovKolmogorov, Dinic, Graph, MaxFlowError, MaxFlowSolver, Pseudoflow, PushRelabelFIFO,
+    PushRelabelHighestLabel,
+};
+
+fn new_solver(algorithm: usize, num_left_nodes: usize, num_right_nodes: usize, graph: &mut Graph) -> Box<dyn MaxFlowSolver + '_> {
+    match algorithm {
+        0 => Box::new(Dinic::new(graph).unwrap()),
+        1 => Box::new(BipartitePushRelabelFIFO::new(num_left_nodes, num_right_nodes, graph).unwrap()),
+        _ => Box::new(BipartitePushRelabelHighestLabel::new(num_left_nodes, num_right_nodes, graph).unwrap()),
+    }
+}
+
+#[test]
+fn warm_start_matches_cold_solve() {
+    for algorithm in 0..3 {
+        let instance = generators::rope(40, 200, 4, &mut Random::new(3)).unwrap();
+        let (source, sink) = (instance.source(), instance.sink());
+        let mut graph = instance.to_graph();
+        graph.set_validation(true);
+        let mut solver = new_solver(algorithm, instance.num_left_nodes, instance.num_right_nodes, &mut graph);
+        solver.solve(source, sink).unwrap();
+
+        // both lowered and raised capacities, on terminal and inner edges
+        let mut rng = Random::new(11);
+        for _ in 0..5 {
+            for _ in 0..20 {
+                let edge_index = rng.gen_range(0, solver.graph().num_edges() as u64 - 1) as usize;
+                let capacity = rng.gen_range(0, 1 << 24) as i64;
+                solver.set_capacity(edge_index, capacity).unwrap();
+            }
+            let value = solver.solve(source, sink).unwrap();
+            assert_eq!(value, dinic(solver.graph(), &[source], &[sink]), "algorithm {}", algorithm);
+            assert_eq!(value, solver.flow_value(sink));
+        }
+    }
+}
+
+#[test]
+fn lowered_capacity_is_repaired() {
+    let mut graph = Graph::new();
+    let a = graph.add_directed_edge(0, 1, 4).unwrap();
+    graph.add_directed_edge(0, 2, 2);
+    graph.add_directed_edge(1, 3, 4);
+    graph.add_directed_edge(2, 3, 4);
+    graph.add_directed_edge(1, 2, 3);
+    graph.set_validation(true);
+    let mut solver = Dinic::new(&mut graph).unwrap();
+    assert_eq!(solver.solve(0, 3).unwrap(), 6);
+
+    solver.set_capacity(a, 1).unwrap();
+    assert_eq!(solver.solve(0, 3).unwrap(), 3);
+    solver.set_capacity(a, 10).unwrap();
+    assert_eq!(solver.solve(0, 3).unwrap(), 8);
+    assert!(solver.set_capacity(a, -1).is_err());
+}
+
+#[test]
+fn min_cut_needs_a_solve_after_changes() {
+    let mut graph = Graph::new();
+    graph.add_directed_edge(0, 1, 2);
+    graph.add_directed_edge(1, 2, 1);
+    let mut solver = Dinic::new(&mut graph).unwrap();
+    assert_eq!(solver.solve(0, 2).unwrap(), 1);
+    assert_eq!(solver.min_cut(0).unwrap().edges, vec![1]);
+    assert!(matches!(solver.min_cut(3), Err(MaxFlowError::NodeOutOfRange { node: 3, .. })));
+
+    // the graph is taken apart by the change until the next solve
+    let node = solver.graph_mut().add_node();
+    solver.graph_mut().add_directed_edge(1, node, 5);
+    assert_eq!(solver.min_cut(0), Err(MaxFlowError::NotBuilt));
+    assert_eq!(solver.min_cut_multi(&[0]), Err(MaxFlowError::NotBuilt));
+    solver.graph_mut().remove_edge(1).unwrap();
+    assert_eq!(solver.graph().min_cut(0), Err(MaxFlowError::NotBuilt));
+    assert_eq!(solver.solve(0, 2).unwrap(), 0);
+    assert_eq!(solver.min_cut(0).unwrap().source_side, vec![0, 1, 3]);
+}
+
+fn new_general_solver(algorithm: usize, graph: &mut Graph) -> Box<dyn MaxFlowSolver + '_> {
+    match algorithm {
+        0 => Box::new(Dinic::new(graph).unwrap()),
+        1 => Box::new(PushRelabelFIFO::new(graph).unwrap()),
+        2 => Box::new(PushRelabelHighestLabel::new(graph).unwrap()),
+        3 => Box::new(Pseudoflow::new(graph).unwrap()),
+        _ => Box::new(BoykovKolmogorov::new(graph).unwrap()),
+    }
+}
+
+#[test]
+fn removed_edges_lose_their_flow() {
+    let mut rng = Random::new(31);
+    for round in 0..100 {
+        let n = rng.gen_range(4, 20) as usize;
+        let mut graph = random_graph(&mut rng, n, 80, round % 5 != 4);
+        graph.set_validation(true);
+        let mut solver = new_general_solver(round % 5, &mut graph);
+        solver.solve(0, n - 1).unwrap();
+        let before: Vec<_> = (0..solver.graph().num_edges()).map(|edge_index| solver.get_edge(edge_index)).collect();
+        if before.is_empty() {
+            continue;
+        }
+
+        // removals alone, then removals with additions, which build the graph again
+        for step in 0..2 {
+            let mut removed = vec![];
+            for _ in 0..3 {
+                let edge_index = rng.gen_range(0, before.len() as u64 - 1) as usize;
+                if !solver.graph().is_removed(edge_index) {
+                    solver.graph_mut().remove_edge(edge_index).unwrap();
+                    removed.push(edge_index);
+                }
+            }
+            if step == 1 {
+                let node = solver.graph_mut().add_node();
+                solver.graph_mut().add_directed_edge(0, node, 7);
+                solver.graph_mut().add_directed_edge(node, n - 1, 5);
+            }
+            let want = dinic(solver.graph(), &[0], &[n - 1]);
+            assert_eq!(solver.solve(0, n - 1).unwrap(), want, "round {}", round);
+            for &edge_index in removed.iter() {
+                assert_eq!(solver.get_edge(edge_index).flow, 0);
+                assert_eq!(solver.get_edge(edge_index).capacity, 0);
+                assert!(solver.min_cut(0).unwrap().edges.iter().all(|&cut_edge| cut_edge != edge_index));
+            }
+        }
+
+        // the other edges keep their index, undirected ones may have turned around with their flow
+        for (edge_index, e) in before.iter().enumerate().filter(|&(edge_index, _)| !solver.graph().is_removed(edge_index)) {
+            let now = solver.get_edge(edge_index);
+            assert_eq!(now.capacity, e.capacity);
+            assert!((now.from, now.to) == (e.from, e.to) || solver.graph().is_undirected(edge_index) && (now.to, now.from) == (e.from, e.to));
+        }
+    }
+}
+
+#[test]
+fn removed_node_has_no_flow() {
+    let mut rng = Random::new(37);
+    for round in 0..50 {
+        let n = rng.gen_range(4, 20) as usize;
+        let mut graph = random_graph(&mut rng, n, 80, false);
+        graph.set_validation(true);
+        let mut solver = new_general_solver(round % 5, &mut graph);
+        solver.solve(0, n - 1).unwrap();
+
+        let u = rng.gen_range(1, n as u64 - 2) as usize;
+        solver.graph_mut().remove_node(u).unwrap();
+        let want = dinic(solver.graph(), &[0], &[n - 1]);
+        assert_eq!(solver.solve(0, n - 1).unwrap(), want);
+        for edge_index in 0..solver.graph().num_edges() {
+            let e = solver.get_edge(edge_index);
+            assert!(e.from != u && e.to != u || solver.graph().is_removed(edge_index) && e.flow == 0);
+        }
+    }
+}